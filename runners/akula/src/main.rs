@@ -1,15 +1,24 @@
-use std::{fs, path::PathBuf, str::FromStr, time::Instant};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use akula::{
     execution::{
         address::create_address,
         evm::{
-            util::mocked_host::MockedHost, AnalyzedCode, CallKind, InterpreterMessage, StatusCode,
+            util::mocked_host::{MockedHost, StorageValue},
+            AnalyzedCode, CallKind, InterpreterMessage, StatusCode,
         },
     },
     models::{Address, Revision, U256},
 };
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
 
 /// Akula runner interface
 #[derive(Parser, Debug)]
@@ -20,12 +29,101 @@ struct Args {
     contract_code_path: PathBuf,
 
     /// Hex of calldata to use when calling the contract
-    #[arg(long)]
-    calldata: String,
+    #[arg(long, required_unless_present = "calldata_path")]
+    calldata: Option<String>,
+
+    /// Path to the hex calldata to use when calling the contract, instead of `--calldata`
+    #[arg(long, conflicts_with = "calldata")]
+    calldata_path: Option<PathBuf>,
+
+    /// What to time: calls to the deployed contract, its deployment, or both, or how many calls
+    /// to the deployed contract can be made per second
+    #[arg(long, value_enum, default_value_t = Mode::Call)]
+    mode: Mode,
 
     /// Number of times to run the benchmark
     #[arg(short, long, default_value_t = 1)]
-    num_runs: u8,
+    num_runs: u64,
+
+    /// How long to call the contract for in each run, in milliseconds, with `--mode throughput`
+    #[arg(long, default_value_t = 1000)]
+    duration_ms: u64,
+
+    /// Number of times to call the contract within each timed run
+    #[arg(long, default_value_t = 1)]
+    inner_iterations: u64,
+
+    /// Gas limit of the benchmark call
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// Chain ID visible to the benchmark
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// Block base fee visible to the benchmark
+    #[arg(long)]
+    base_fee: Option<u64>,
+
+    /// Address that deploys and calls the contract
+    #[arg(long, default_value = CALLER_ADDRESS)]
+    caller: String,
+
+    /// Wei to send along with each call, in decimal
+    #[arg(long)]
+    value: Option<String>,
+
+    /// Path to a JSON file of accounts and storage to load before running
+    #[arg(long)]
+    state_path: Option<PathBuf>,
+
+    /// Hex of calldata of an untimed call to commit to the contract before timing any calls
+    #[arg(long)]
+    setup_calldata: Option<String>,
+
+    /// Print a sentinel instead of the time of any run that takes longer than this
+    #[arg(long)]
+    max_pass_micros: Option<u128>,
+
+    /// Print the return data of the last run to stderr
+    #[arg(long)]
+    print_return_data: bool,
+
+    /// Print a hash of the return data of every run to stderr
+    #[arg(long)]
+    print_return_data_hashes: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Call,
+    Deploy,
+    Both,
+    Throughput,
+}
+
+/// Accounts and storage to load before running, in the format evm-bench writes
+#[derive(Debug, Default, Deserialize)]
+struct InitialState {
+    #[serde(default)]
+    accounts: Vec<InitialAccount>,
+    #[serde(default)]
+    storage: Vec<InitialStorage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitialAccount {
+    address: String,
+    balance: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitialStorage {
+    /// The benchmark contract if not given
+    address: Option<String>,
+    slot: String,
+    value: String,
 }
 
 const CALLER_ADDRESS: &str = "0x1000000000000000000000000000000000000001";
@@ -33,54 +131,197 @@ const CALLER_ADDRESS: &str = "0x1000000000000000000000000000000000000001";
 fn main() {
     let args = Args::parse();
 
-    let caller_address = Address::from_str(CALLER_ADDRESS).unwrap();
+    let caller_address = Address::from_str(&args.caller).expect("could not parse caller address");
     let contract_address = create_address(caller_address, 0);
 
     let contract_code =
         hex::decode(fs::read_to_string(args.contract_code_path).expect("unable to open file"))
             .expect("could not hex decode contract code");
-    let calldata = hex::decode(args.calldata).expect("could not hex decode calldata");
+    let calldata = hex::decode(match args.calldata_path {
+        Some(calldata_path) => fs::read_to_string(calldata_path)
+            .expect("unable to open file")
+            .trim()
+            .to_string(),
+        None => args.calldata.unwrap(),
+    })
+    .expect("could not hex decode calldata");
+    let initial_state: InitialState = args.state_path.map_or_else(Default::default, |state_path| {
+        serde_json::from_str(&fs::read_to_string(state_path).expect("unable to open file"))
+            .expect("could not parse initial state")
+    });
+    let parse_word = |word: &str| {
+        U256::from_str_radix(word.trim_start_matches("0x"), 16).expect("could not parse word")
+    };
+    // Storage of the benchmark contract can only be loaded once it has been created
+    let load_storage = |host: &mut MockedHost, contract_address: Option<Address>| {
+        for entry in &initial_state.storage {
+            let address = match (&entry.address, contract_address) {
+                (Some(address), None) => {
+                    Address::from_str(address).expect("could not parse storage address")
+                }
+                (None, Some(contract_address)) => contract_address,
+                _ => continue,
+            };
+            host.accounts.entry(address).or_default().storage.insert(
+                parse_word(&entry.slot),
+                StorageValue {
+                    value: parse_word(&entry.value),
+                    ..Default::default()
+                },
+            );
+        }
+    };
 
     // Set up the EVM with a database and create the contract
-    let mut host = MockedHost::default();
+    let new_host = || {
+        let mut host = MockedHost::default();
+        if let Some(chain_id) = args.chain_id {
+            host.tx_context.chain_id = U256::from(chain_id);
+        }
+        if let Some(base_fee) = args.base_fee {
+            host.tx_context.block_base_fee = U256::from(base_fee);
+        }
+        for account in &initial_state.accounts {
+            let loaded = host
+                .accounts
+                .entry(
+                    Address::from_str(&account.address).expect("could not parse account address"),
+                )
+                .or_default();
+            if let Some(balance) = &account.balance {
+                loaded.balance =
+                    U256::from_str_radix(balance, 10).expect("could not parse balance");
+            }
+            if let Some(code) = &account.code {
+                loaded.code = hex::decode(code.trim_start_matches("0x"))
+                    .expect("could not hex decode account code")
+                    .into();
+            }
+        }
+        // Like revm, give a caller that pays for gas or sends value plenty of balance unless the
+        // initial state gives it one, so that both see the same balance
+        let caller_funded = initial_state.accounts.iter().any(|account| {
+            account.balance.is_some()
+                && Address::from_str(&account.address).ok() == Some(caller_address)
+        });
+        if !caller_funded && (args.base_fee.is_some() || args.value.is_some()) {
+            host.accounts.entry(caller_address).or_default().balance = U256::MAX >> 1;
+        }
+        load_storage(&mut host, None);
+        host
+    };
+    let create_message = InterpreterMessage {
+        kind: CallKind::Call,
+        is_static: false,
+        depth: 0,
+        gas: i64::MAX,
+        recipient: contract_address,
+        sender: caller_address,
+        code_address: contract_address,
+        real_sender: caller_address,
+        input_data: Default::default(),
+        value: U256::ZERO,
+    };
+
+    let mut gas_used = 0;
+    if matches!(args.mode, Mode::Deploy | Mode::Both) {
+        for _ in 0..args.num_runs {
+            // Every run deploys onto a fresh host, as the contract would be deployed for real
+            let mut deploy_host = new_host();
+            let timer = Instant::now();
+            for _ in 1..args.inner_iterations {
+                AnalyzedCode::analyze(contract_code.as_slice()).execute(
+                    &mut deploy_host,
+                    &create_message,
+                    Revision::London,
+                );
+            }
+            let deploy_result = AnalyzedCode::analyze(contract_code.as_slice()).execute(
+                &mut deploy_host,
+                &create_message,
+                Revision::London,
+            );
+            let dur = timer.elapsed();
+
+            match deploy_result.status_code {
+                StatusCode::Success => {}
+                reason => panic!("unexpected exit reason while deploying: {:?}", reason),
+            }
+            gas_used = create_message.gas - deploy_result.gas_left;
+
+            println!("evm-bench-deploy-time: {}", dur.as_micros() as f64 / 1e3);
+        }
+        if args.mode == Mode::Deploy {
+            eprintln!("evm-bench-gas-used: {gas_used}");
+            print_peak_memory();
+            return;
+        }
+    }
+
+    let mut host = new_host();
     let create_result = AnalyzedCode::analyze(contract_code.as_slice()).execute(
         &mut host,
-        &InterpreterMessage {
-            kind: CallKind::Call,
-            is_static: false,
-            depth: 0,
-            gas: i64::MAX,
-            recipient: contract_address,
-            sender: caller_address,
-            code_address: contract_address,
-            real_sender: caller_address,
-            input_data: Default::default(),
-            value: U256::ZERO,
-        }
-        .into(),
+        &create_message,
         Revision::London,
     );
     match create_result.status_code {
         StatusCode::Success => {}
         reason => panic!("unexpected exit reason while creating: {:?}", reason),
     }
+    load_storage(&mut host, Some(contract_address));
 
     let call_analyzed = AnalyzedCode::analyze(&create_result.output_data);
     let call_message = InterpreterMessage {
         kind: CallKind::Call,
         is_static: false,
         depth: 0,
-        gas: i64::MAX,
+        gas: args.gas_limit.map_or(i64::MAX, |gas_limit| {
+            i64::try_from(gas_limit).expect("gas limit does not fit in a signed 64-bit integer")
+        }),
         recipient: contract_address,
         sender: caller_address,
         code_address: contract_address,
         real_sender: caller_address,
         input_data: calldata.into(),
-        value: U256::ZERO,
+        value: args.value.as_deref().map_or(U256::ZERO, |value| {
+            U256::from_str_radix(value, 10).expect("could not parse value")
+        }),
     };
+    if let Some(setup_calldata) = &args.setup_calldata {
+        let setup_message = InterpreterMessage {
+            input_data: hex::decode(setup_calldata.trim_start_matches("0x"))
+                .expect("could not hex decode setup calldata")
+                .into(),
+            ..call_message.clone()
+        };
+        let setup_result = call_analyzed.execute(&mut host, &setup_message, Revision::London);
+        match setup_result.status_code {
+            StatusCode::Success => {}
+            reason => panic!("unexpected exit reason while setting up: {:?}", reason),
+        }
+    }
+    // Calls write straight into the host, so with a setup call every run starts from a copy of
+    // the state it left rather than from whatever earlier runs left
+    let setup_host = args.setup_calldata.is_some().then(|| host.clone());
 
+    let mut return_data = Default::default();
+    let duration = Duration::from_millis(args.duration_ms);
     for _ in 0..args.num_runs {
+        if let Some(setup_host) = &setup_host {
+            host = setup_host.clone();
+        }
         let timer = Instant::now();
+        let mut iterations = 1;
+        if args.mode == Mode::Throughput {
+            while timer.elapsed() < duration {
+                call_analyzed.execute(&mut host, &call_message, Revision::London);
+                iterations += 1;
+            }
+        } else {
+            for _ in 1..args.inner_iterations {
+                call_analyzed.execute(&mut host, &call_message, Revision::London);
+            }
+        }
         let call_result = call_analyzed.execute(&mut host, &call_message, Revision::London);
         let dur = timer.elapsed();
 
@@ -88,7 +329,52 @@ fn main() {
             StatusCode::Success => {}
             reason => panic!("unexpected exit reason while benchmarking: {:?}", reason),
         }
+        gas_used = call_message.gas - call_result.gas_left;
+        return_data = call_result.output_data;
+        if args.print_return_data_hashes {
+            let mut hasher = DefaultHasher::new();
+            return_data.hash(&mut hasher);
+            eprintln!("evm-bench-return-data-hash: {:016x}", hasher.finish());
+        }
+
+        if args.mode == Mode::Throughput {
+            println!(
+                "evm-bench-iterations-per-sec: {}",
+                iterations as f64 / dur.as_secs_f64()
+            );
+        } else if args
+            .max_pass_micros
+            .is_some_and(|max_pass_micros| dur.as_micros() > max_pass_micros)
+        {
+            println!("evm-bench-skipped-pass");
+        } else {
+            println!("{}", dur.as_micros() as f64 / 1e3)
+        }
+    }
+
+    if args.print_return_data {
+        eprintln!("evm-bench-return-data: {}", hex::encode(return_data));
+    }
+    eprintln!("evm-bench-gas-used: {gas_used}");
+    print_peak_memory();
+}
 
-        println!("{}", dur.as_micros() as f64 / 1e3)
+/// Prints the peak resident memory of this process to stderr, where the OS reports it, so that
+/// toolchains the entry runs are not counted
+fn print_peak_memory() {
+    let peak_memory_kb = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmHWM:"))?
+                .trim()
+                .strip_suffix("kB")?
+                .trim()
+                .parse::<u64>()
+                .ok()
+        });
+    if let Some(peak_memory_kb) = peak_memory_kb {
+        eprintln!("evm-bench-peak-memory-bytes: {}", peak_memory_kb * 1024);
     }
 }
@@ -1,8 +1,20 @@
-use std::{fs, path::PathBuf, str::FromStr, time::Instant};
+use std::{
+    cmp::Reverse,
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use bytes::Bytes;
-use clap::Parser;
-use revm::{InMemoryDB, Return, TransactOut, TransactTo, B160};
+use clap::{Parser, ValueEnum};
+use revm::{
+    opcode::OPCODE_JUMPMAP, AccountInfo, Bytecode, Database, EVMData, InMemoryDB, Inspector,
+    Interpreter, Return, TransactOut, TransactTo, B160, U256,
+};
+use serde::Deserialize;
 
 extern crate alloc;
 
@@ -15,12 +27,122 @@ struct Args {
     contract_code_path: PathBuf,
 
     /// Hex of calldata to use when calling the contract
-    #[arg(long)]
-    calldata: String,
+    #[arg(long, required_unless_present = "calldata_path")]
+    calldata: Option<String>,
+
+    /// Path to the hex calldata to use when calling the contract, instead of `--calldata`
+    #[arg(long, conflicts_with = "calldata")]
+    calldata_path: Option<PathBuf>,
+
+    /// What to time: calls to the deployed contract, its deployment, or both, or how many calls
+    /// to the deployed contract can be made per second
+    #[arg(long, value_enum, default_value_t = Mode::Call)]
+    mode: Mode,
 
     /// Number of times to run the benchmark
     #[arg(short, long, default_value_t = 1)]
-    num_runs: u8,
+    num_runs: u64,
+
+    /// How long to call the contract for in each run, in milliseconds, with `--mode throughput`
+    #[arg(long, default_value_t = 1000)]
+    duration_ms: u64,
+
+    /// Number of times to call the contract within each timed run
+    #[arg(long, default_value_t = 1)]
+    inner_iterations: u64,
+
+    /// Gas limit of the benchmark call
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// Chain ID visible to the benchmark
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// Block base fee visible to the benchmark
+    #[arg(long)]
+    base_fee: Option<u64>,
+
+    /// Address that deploys and calls the contract
+    #[arg(long, default_value = CALLER_ADDRESS)]
+    caller: String,
+
+    /// Wei to send along with each call, in decimal
+    #[arg(long)]
+    value: Option<String>,
+
+    /// Path to a JSON file of accounts and storage to load before running
+    #[arg(long)]
+    state_path: Option<PathBuf>,
+
+    /// Hex of calldata of an untimed call to commit to the contract before timing any calls
+    #[arg(long)]
+    setup_calldata: Option<String>,
+
+    /// Print a sentinel instead of the time of any run that takes longer than this
+    #[arg(long)]
+    max_pass_micros: Option<u128>,
+
+    /// Print the return data of the last run to stderr
+    #[arg(long)]
+    print_return_data: bool,
+
+    /// Print a hash of the return data of every run to stderr
+    #[arg(long)]
+    print_return_data_hashes: bool,
+
+    /// Print how many times an untimed extra call executes each opcode to stderr
+    #[arg(long)]
+    profile: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Call,
+    Deploy,
+    Both,
+    Throughput,
+}
+
+/// Accounts and storage to load before running, in the format evm-bench writes
+#[derive(Debug, Default, Deserialize)]
+struct InitialState {
+    #[serde(default)]
+    accounts: Vec<InitialAccount>,
+    #[serde(default)]
+    storage: Vec<InitialStorage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitialAccount {
+    address: String,
+    balance: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitialStorage {
+    /// The benchmark contract if not given
+    address: Option<String>,
+    slot: String,
+    value: String,
+}
+
+/// Counts how many times each opcode is executed
+struct OpcodeCounter<'a> {
+    counts: &'a mut [u64; 256],
+}
+
+impl<DB: Database> Inspector<DB> for OpcodeCounter<'_> {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> Return {
+        self.counts[interp.current_opcode() as usize] += 1;
+        Return::Continue
+    }
 }
 
 const CALLER_ADDRESS: &str = "0x1000000000000000000000000000000000000001";
@@ -28,22 +150,112 @@ const CALLER_ADDRESS: &str = "0x1000000000000000000000000000000000000001";
 fn main() {
     let args = Args::parse();
 
-    let caller_address = B160::from_str(CALLER_ADDRESS).unwrap();
+    let caller_address = B160::from_str(&args.caller).expect("could not parse caller address");
 
     let contract_code: Bytes =
         hex::decode(fs::read_to_string(args.contract_code_path).expect("unable to open file"))
             .expect("could not hex decode contract code")
             .into();
-    let calldata: Bytes = hex::decode(args.calldata)
-        .expect("could not hex decode calldata")
-        .into();
+    let calldata: Bytes = hex::decode(match args.calldata_path {
+        Some(calldata_path) => fs::read_to_string(calldata_path)
+            .expect("unable to open file")
+            .trim()
+            .to_string(),
+        None => args.calldata.unwrap(),
+    })
+    .expect("could not hex decode calldata")
+    .into();
+    let initial_state: InitialState = args.state_path.map_or_else(Default::default, |state_path| {
+        serde_json::from_str(&fs::read_to_string(state_path).expect("unable to open file"))
+            .expect("could not parse initial state")
+    });
 
     // Set up the EVM with a database and create the contract
     let mut evm = revm::new();
     evm.database(InMemoryDB::default());
+    let db = evm.db().unwrap();
+    for account in &initial_state.accounts {
+        let balance = account.balance.as_deref().map_or(U256::ZERO, |balance| {
+            U256::from_str(balance).expect("could not parse balance")
+        });
+        let code = account.code.as_deref().map_or_else(Bytes::new, |code| {
+            hex::decode(code.trim_start_matches("0x"))
+                .expect("could not hex decode account code")
+                .into()
+        });
+        db.insert_account_info(
+            B160::from_str(&account.address).expect("could not parse account address"),
+            AccountInfo::new(balance, 0, Bytecode::new_raw(code)),
+        );
+    }
+    // revm charges the caller up front for all the gas a transaction could use and the value it
+    // sends, so once there is either, a caller the initial state gives no balance is given plenty
+    let caller_funded = initial_state.accounts.iter().any(|account| {
+        account.balance.is_some() && B160::from_str(&account.address).ok() == Some(caller_address)
+    });
+    if !caller_funded && (args.base_fee.is_some() || args.value.is_some()) {
+        db.insert_account_info(
+            caller_address,
+            AccountInfo::new(U256::MAX >> 1, 0, Bytecode::new_raw(Bytes::new())),
+        );
+    }
+    // Storage of the benchmark contract can only be loaded once it has been created
+    let load_storage = |db: &mut InMemoryDB, contract_address: Option<B160>| {
+        for entry in &initial_state.storage {
+            let address = match (&entry.address, contract_address) {
+                (Some(address), None) => {
+                    B160::from_str(address).expect("could not parse storage address")
+                }
+                (None, Some(contract_address)) => contract_address,
+                _ => continue,
+            };
+            db.insert_account_storage(
+                address,
+                U256::from_str(&entry.slot).expect("could not parse storage slot"),
+                U256::from_str(&entry.value).expect("could not parse storage value"),
+            )
+            .expect("could not load storage");
+        }
+    };
+    load_storage(db, None);
+    if let Some(chain_id) = args.chain_id {
+        evm.env.cfg.chain_id = U256::from(chain_id);
+    }
+    if let Some(base_fee) = args.base_fee {
+        evm.env.block.basefee = U256::from(base_fee);
+        // Transactions priced below the base fee are rejected
+        evm.env.tx.gas_price = U256::from(base_fee);
+    }
     evm.env.tx.caller = caller_address;
     evm.env.tx.transact_to = TransactTo::create();
     evm.env.tx.data = contract_code;
+
+    let mut gas_used = 0;
+    if matches!(args.mode, Mode::Deploy | Mode::Both) {
+        // Creating without committing leaves the database as it was, so every run deploys anew
+        for _ in 0..args.num_runs {
+            let timer = Instant::now();
+            for _ in 1..args.inner_iterations {
+                evm.transact();
+            }
+            let (res, _) = evm.transact();
+            let dur = timer.elapsed();
+
+            match res.exit_reason {
+                Return::Continue => {}
+                reason => panic!("unexpected exit reason while deploying: {:?}", reason),
+            }
+            gas_used = res.gas_used;
+
+            println!("evm-bench-deploy-time: {}", dur.as_micros() as f64 / 1e3);
+        }
+        if args.mode == Mode::Deploy {
+            eprintln!("evm-bench-gas-used: {gas_used}");
+            print_peak_memory();
+            return;
+        }
+    }
+
     let res = evm.transact_commit();
     match res.exit_reason {
         Return::Continue => {}
@@ -53,13 +265,44 @@ fn main() {
         TransactOut::Create(_, Some(addr)) => addr,
         _ => panic!("could not get contract address"),
     };
+    load_storage(evm.db().unwrap(), Some(contract_address));
 
     evm.env.tx.caller = caller_address;
     evm.env.tx.transact_to = TransactTo::Call(contract_address);
+    if let Some(gas_limit) = args.gas_limit {
+        evm.env.tx.gas_limit = gas_limit;
+    }
+    if let Some(value) = &args.value {
+        evm.env.tx.value = U256::from_str(value).expect("could not parse value");
+    }
+    if let Some(setup_calldata) = &args.setup_calldata {
+        // Timed calls are not committed, so each of them starts from the state this call leaves
+        evm.env.tx.data = hex::decode(setup_calldata.trim_start_matches("0x"))
+            .expect("could not hex decode setup calldata")
+            .into();
+        let res = evm.transact_commit();
+        match res.exit_reason {
+            Return::Return | Return::Stop => (),
+            reason => panic!("unexpected exit reason while setting up: {:?}", reason),
+        }
+    }
     evm.env.tx.data = calldata;
 
+    let mut return_data = Bytes::new();
+    let duration = Duration::from_millis(args.duration_ms);
     for _ in 0..args.num_runs {
         let timer = Instant::now();
+        let mut iterations = 1;
+        if args.mode == Mode::Throughput {
+            while timer.elapsed() < duration {
+                evm.transact();
+                iterations += 1;
+            }
+        } else {
+            for _ in 1..args.inner_iterations {
+                evm.transact();
+            }
+        }
         let (res, _) = evm.transact();
         let dur = timer.elapsed();
 
@@ -69,7 +312,73 @@ fn main() {
                 panic!("unexpected exit reason while benchmarking: {:?}", reason)
             }
         }
+        gas_used = res.gas_used;
+        if let TransactOut::Call(out) = res.out {
+            return_data = out;
+        }
+        if args.print_return_data_hashes {
+            let mut hasher = DefaultHasher::new();
+            return_data.hash(&mut hasher);
+            eprintln!("evm-bench-return-data-hash: {:016x}", hasher.finish());
+        }
+
+        if args.mode == Mode::Throughput {
+            println!(
+                "evm-bench-iterations-per-sec: {}",
+                iterations as f64 / dur.as_secs_f64()
+            );
+        } else if args
+            .max_pass_micros
+            .is_some_and(|max_pass_micros| dur.as_micros() > max_pass_micros)
+        {
+            println!("evm-bench-skipped-pass");
+        } else {
+            println!("{}", dur.as_micros() as f64 / 1e3)
+        }
+    }
+
+    if args.profile {
+        // Counting slows execution down, so it is done on a call of its own
+        let mut counts = [0; 256];
+        evm.inspect(OpcodeCounter {
+            counts: &mut counts,
+        });
+        let mut counts = counts
+            .into_iter()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        counts.sort_by_key(|(_, count)| Reverse(*count));
+        for (opcode, count) in counts {
+            let name =
+                OPCODE_JUMPMAP[opcode].map_or_else(|| format!("0x{opcode:02x}"), str::to_string);
+            eprintln!("evm-bench-opcode-count: {name} {count}");
+        }
+    }
+
+    if args.print_return_data {
+        eprintln!("evm-bench-return-data: {}", hex::encode(return_data));
+    }
+    eprintln!("evm-bench-gas-used: {gas_used}");
+    print_peak_memory();
+}
 
-        println!("{}", dur.as_micros() as f64 / 1e3)
+/// Prints the peak resident memory of this process to stderr, where the OS reports it, so that
+/// toolchains the entry runs are not counted
+fn print_peak_memory() {
+    let peak_memory_kb = fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmHWM:"))?
+                .trim()
+                .strip_suffix("kB")?
+                .trim()
+                .parse::<u64>()
+                .ok()
+        });
+    if let Some(peak_memory_kb) = peak_memory_kb {
+        eprintln!("evm-bench-peak-memory-bytes: {}", peak_memory_kb * 1024);
     }
 }
@@ -1,26 +1,57 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeSet, HashMap, HashSet},
     error,
-    fs::create_dir_all,
+    fs::{self, create_dir_all},
+    io::{BufRead, BufReader},
     path::{Path, PathBuf},
-    process::Command,
+    process::{self, Command, Stdio},
+    time::{Duration, Instant},
 };
 
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 use users::{get_current_gid, get_current_uid};
 
-use crate::metadata::Benchmark;
+use crate::{
+    error::EvmBenchError,
+    exec::{interrupted, output_with_peak_memory},
+    metadata::{Benchmark, BenchmarkMode, EvmConfig, InitialState, Runner},
+    progress::{Progress, ProgressState},
+};
+
+/// How Solidity contracts are compiled.
+#[derive(Clone, Debug)]
+pub enum Solc {
+    /// In an `ethereum/solc` container of each benchmark's `solc` version, run with this Docker
+    /// executable.
+    Docker(PathBuf),
+    /// With this `solc` executable, whatever `solc` version benchmarks ask for, for builds that
+    /// need neither Docker nor the network.
+    Native(PathBuf),
+}
 
 #[derive(Clone, Debug)]
 struct BuildContext {
-    docker_executable: PathBuf,
+    solc: Solc,
     contract_path: PathBuf,
     contract_context_path: PathBuf,
     build_path: PathBuf,
+    allow_network: bool,
+    vyper_executable: Option<PathBuf>,
+    timeout: Option<Duration>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct BuildResult {
     pub contract_bin_path: PathBuf,
+    /// Keccak256 hash of the compiled bytecode followed by the calldata, which is the same for
+    /// any two benchmarks that would benchmark exactly the same thing.
+    pub bytecode_hash: String,
+    /// Non-fatal diagnostics from the compiler.
+    pub warnings: Vec<String>,
+    /// How long building took, including any container overhead.
+    pub build_time: Duration,
 }
 
 #[derive(Debug)]
@@ -29,10 +60,76 @@ pub struct BuiltBenchmark {
     pub result: BuildResult,
 }
 
+/// A benchmark that could not be built, and why.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BuildFailure {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct BuiltBenchmarks {
+    pub built: Vec<BuiltBenchmark>,
+    pub failed: Vec<BuildFailure>,
+}
+
+/// Largest deployed bytecode mainnet accepts, in bytes (EIP-170).
+const MAX_DEPLOYED_BYTECODE_BYTES: usize = 24576;
+
+/// Checks the hex deployed bytecode of a benchmark contract, which is an error if there is none,
+/// as for an abstract contract, and worth a warning if it is too large to deploy on mainnet.
+fn check_deployed_bytecode(
+    benchmark: &Benchmark,
+    deployed_bytecode: &str,
+) -> Result<Option<String>, Box<dyn error::Error>> {
+    let size = deployed_bytecode.trim().trim_start_matches("0x").len() / 2;
+    if size == 0 {
+        return Err(format!(
+            "benchmark {} has no deployed bytecode, is its contract abstract?",
+            benchmark.name
+        )
+        .into());
+    }
+    if size > MAX_DEPLOYED_BYTECODE_BYTES {
+        let warning = format!(
+            "deployed bytecode is {size} bytes, over the {MAX_DEPLOYED_BYTECODE_BYTES}-byte EIP-170 limit, so the contract could not be deployed on mainnet"
+        );
+        log::warn!("benchmark {}: {warning}", benchmark.name);
+        return Ok(Some(warning));
+    }
+    Ok(None)
+}
+
+fn bytecode_hash(
+    contract_bin_path: &Path,
+    calldata: &[u8],
+) -> Result<String, Box<dyn error::Error>> {
+    let mut hasher = Keccak256::new();
+    hasher.update(fs::read(contract_bin_path)?);
+    hasher.update(calldata);
+    Ok(hex::encode(hasher.finalize()))
+}
+
 fn build_benchmark(
     benchmark: &Benchmark,
     build_context: &BuildContext,
+) -> Result<BuiltBenchmark, EvmBenchError> {
+    if benchmark.is_vyper() {
+        build_vyper_benchmark(benchmark, build_context)
+    } else {
+        build_solidity_benchmark(benchmark, build_context)
+    }
+    .map_err(EvmBenchError::Compile)
+}
+
+fn build_vyper_benchmark(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
 ) -> Result<BuiltBenchmark, Box<dyn error::Error>> {
+    let vyper_executable = build_context
+        .vyper_executable
+        .as_ref()
+        .ok_or("no vyper executable available to build a vyper contract")?;
     let contract_name = benchmark
         .contract
         .file_name()
@@ -41,23 +138,233 @@ fn build_benchmark(
         .to_string();
 
     log::info!(
-        "building benchmark {} ({contract_name} w/ solc@{})...",
+        "building benchmark {} ({contract_name} w/ {})...",
         benchmark.name,
-        benchmark.solc_version
+        vyper_executable.display()
     );
 
     let relative_contract_path = build_context
         .contract_path
         .strip_prefix(&build_context.contract_context_path)?;
 
+    create_dir_all(&build_context.build_path)?;
+
+    let (out, _) = output_with_peak_memory(
+        Command::new(vyper_executable)
+            .current_dir(&build_context.contract_context_path)
+            .args(["-f", "bytecode,bytecode_runtime"])
+            .arg(relative_contract_path),
+        build_context.timeout,
+    )?;
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    log::trace!("stdout: {}", stdout);
+    log::trace!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+
+    if out.status.success() {
+        let mut lines = stdout.lines();
+        let bytecode = lines.next().ok_or("vyper produced no bytecode")?;
+        let deployed_bytecode = lines.next().ok_or("vyper produced no deployed bytecode")?;
+        let warnings = check_deployed_bytecode(benchmark, deployed_bytecode)?
+            .into_iter()
+            .collect();
+
+        let mut contract_bin_path = build_context.build_path.join(&contract_name);
+        contract_bin_path.set_extension("bin");
+        // Match the `solc` output format runners expect: bare hex without a `0x` prefix.
+        fs::write(&contract_bin_path, bytecode.trim().trim_start_matches("0x"))?;
+
+        log::debug!("built benchmark {}", benchmark.name);
+        Ok(BuiltBenchmark {
+            benchmark: Benchmark {
+                compiler_version: vyper_version(vyper_executable),
+                ..benchmark.clone()
+            },
+            result: BuildResult {
+                bytecode_hash: bytecode_hash(&contract_bin_path, &benchmark.calldata)?,
+                contract_bin_path,
+                warnings,
+                build_time: Duration::ZERO,
+            },
+        })
+    } else {
+        Err(format!("{}", out.status).into())
+    }
+}
+
+/// Splits `solc` output into its individual diagnostics, returning warnings and errors.
+fn parse_solc_diagnostics(stderr: &str) -> (Vec<String>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    for diagnostic in stderr.split("\n\n").map(str::trim) {
+        if diagnostic.starts_with("Warning") {
+            warnings.push(diagnostic.to_string());
+        } else if !diagnostic.is_empty() && !diagnostic.starts_with("Compiler run successful") {
+            errors.push(diagnostic.to_string());
+        }
+    }
+    (warnings, errors)
+}
+
+fn build_solidity_benchmark(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+) -> Result<BuiltBenchmark, Box<dyn error::Error>> {
+    let contract_name = benchmark
+        .contract
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    match &build_context.solc {
+        Solc::Docker(_) => log::info!(
+            "building benchmark {} ({contract_name} w/ solc@{})...",
+            benchmark.name,
+            benchmark.solc_version
+        ),
+        Solc::Native(solc_executable) => log::info!(
+            "building benchmark {} ({contract_name} w/ {})...",
+            benchmark.name,
+            solc_executable.display()
+        ),
+    }
+
+    let relative_contract_path = build_context
+        .contract_path
+        .strip_prefix(&build_context.contract_context_path)?;
+
+    create_dir_all(&build_context.build_path)?;
+
+    let out = match &build_context.solc {
+        Solc::Docker(docker_executable) => run_docker_solc(
+            benchmark,
+            build_context,
+            docker_executable,
+            relative_contract_path,
+        )?,
+        Solc::Native(solc_executable) => {
+            // `solc` runs from the build context, so the build path must not be relative.
+            let build_path = build_context.build_path.canonicalize()?;
+            output_with_peak_memory(
+                Command::new(solc_executable)
+                    .current_dir(&build_context.contract_context_path)
+                    .args(["-o", &build_path.to_string_lossy()])
+                    .args([
+                        "--abi",
+                        "--bin",
+                        "--bin-runtime",
+                        "--metadata",
+                        "--optimize",
+                        "--overwrite",
+                    ])
+                    .arg(relative_contract_path),
+                build_context.timeout,
+            )?
+            .0
+        }
+    };
+
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    log::trace!("stdout: {}", String::from_utf8(out.stdout).unwrap());
+    log::trace!("stderr: {}", stderr);
+
+    let (mut warnings, errors) = parse_solc_diagnostics(&stderr);
+    for warning in &warnings {
+        log::warn!("solc warning in benchmark {}:\n{warning}", benchmark.name);
+    }
+
+    if out.status.success() {
+        let mut contract_bin_path = build_context.build_path.join(&contract_name);
+        contract_bin_path.set_extension("bin");
+        if fs::metadata(&contract_bin_path).map_or(true, |m| m.len() == 0) {
+            return Err(format!(
+                "solc produced no bytecode for benchmark {} at {}, is its contract abstract or named differently from its file?",
+                benchmark.name,
+                contract_bin_path.display()
+            )
+            .into());
+        }
+        warnings.extend(check_deployed_bytecode(
+            benchmark,
+            &fs::read_to_string(contract_bin_path.with_extension("bin-runtime"))?,
+        )?);
+
+        log::debug!("built benchmark {}", benchmark.name);
+        Ok(BuiltBenchmark {
+            benchmark: Benchmark {
+                compiler_version: solc_metadata_version(&contract_bin_path),
+                ..benchmark.clone()
+            },
+            result: BuildResult {
+                bytecode_hash: bytecode_hash(&contract_bin_path, &benchmark.calldata)?,
+                contract_bin_path,
+                warnings,
+                build_time: Duration::ZERO,
+            },
+        })
+    } else if errors.is_empty() {
+        Err(format!("{}", out.status).into())
+    } else {
+        Err(format!("{}:\n{}", out.status, errors.join("\n\n")).into())
+    }
+}
+
+/// Version of `solc` that compiled the contract at `contract_bin_path`, as recorded in the
+/// metadata it writes alongside.
+fn solc_metadata_version(contract_bin_path: &Path) -> Option<String> {
+    let contract_name = contract_bin_path.file_stem()?.to_string_lossy();
+    let metadata_path = contract_bin_path.with_file_name(format!("{contract_name}_meta.json"));
+    let version = fs::read_to_string(&metadata_path)
+        .ok()
+        .and_then(|metadata| serde_json::from_str::<serde_json::Value>(&metadata).ok())
+        .and_then(|metadata| Some(metadata["compiler"]["version"].as_str()?.to_string()));
+    if version.is_none() {
+        log::warn!(
+            "could not read the solc version from {}",
+            metadata_path.display()
+        );
+    }
+    version
+}
+
+fn vyper_version(vyper_executable: &Path) -> Option<String> {
+    let out = Command::new(vyper_executable)
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = String::from_utf8(out.stdout).ok()?.trim().to_string();
+    (out.status.success() && !version.is_empty()).then_some(version)
+}
+
+/// Runs `solc` on a benchmark contract in a container of the benchmark's `solc` version.
+fn run_docker_solc(
+    benchmark: &Benchmark,
+    build_context: &BuildContext,
+    docker_executable: &Path,
+    relative_contract_path: &Path,
+) -> Result<process::Output, Box<dyn error::Error>> {
     let docker_contract_context_path = PathBuf::from("/benchmark");
     let docker_contract_path = docker_contract_context_path.join(relative_contract_path);
     let docker_build_path = PathBuf::from("/build");
 
-    create_dir_all(&build_context.build_path)?;
-
-    let out = Command::new(&build_context.docker_executable)
-        .arg("run")
+    // Killing the Docker client on timeout does not stop the container, so it is named to be
+    // removed explicitly.
+    let container_name = format!(
+        "evm-bench-build-{}-{}",
+        benchmark.name.replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.',
+            "-"
+        ),
+        process::id()
+    );
+    let mut command = Command::new(docker_executable);
+    command.args(["run", "--rm", "--name", &container_name]);
+    command.args(["--label", DOCKER_LABEL]);
+    if !build_context.allow_network {
+        command.args(["--network", "none"]);
+    }
+    command
         .args([
             "-u",
             &format!("{}:{}", get_current_uid(), get_current_gid()),
@@ -78,34 +385,334 @@ fn build_benchmark(
                 docker_build_path.to_string_lossy()
             ),
         ])
-        .arg(format!("ethereum/solc:{}", benchmark.solc_version))
+        .arg(solc_image(&benchmark.solc_version))
         .args(["-o", &docker_build_path.to_string_lossy()])
-        .args(["--abi", "--bin", "--optimize", "--overwrite"])
-        .arg(docker_contract_path)
-        .output()?;
+        .args([
+            "--abi",
+            "--bin",
+            "--bin-runtime",
+            "--metadata",
+            "--optimize",
+            "--overwrite",
+        ])
+        .arg(docker_contract_path);
+    match output_with_peak_memory(&mut command, build_context.timeout) {
+        Ok((out, _)) => Ok(out),
+        Err(e) => {
+            if !docker_succeeds(docker_executable, &["rm", "--force", &container_name])? {
+                log::warn!("could not remove build container {container_name}");
+            }
+            Err(e)
+        }
+    }
+}
 
-    log::trace!("stdout: {}", String::from_utf8(out.stdout).unwrap());
-    log::trace!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+/// Repository of the images `solc` is run from.
+const SOLC_REPOSITORY: &str = "ethereum/solc";
 
-    if out.status.success() {
-        let mut contract_bin_path = build_context.build_path.join(&contract_name);
-        contract_bin_path.set_extension("bin");
+/// Label on every container evm-bench starts, so that they can be told apart from unrelated ones.
+pub(crate) const DOCKER_LABEL: &str = "evm-bench";
 
-        log::debug!("built benchmark {}", benchmark.name);
-        Ok(BuiltBenchmark {
-            benchmark: benchmark.clone(),
-            result: BuildResult { contract_bin_path },
-        })
+fn solc_image(solc_version: &str) -> String {
+    format!("{SOLC_REPOSITORY}:{solc_version}")
+}
+
+/// Removes build containers left behind by evm-bench, e.g. by builds killed on timeout whose
+/// containers could not be removed, and dangling `solc` images, e.g. left behind by pulling newer
+/// images for moving tags like `stable`. Containers and images that evm-bench did not create are
+/// never touched, so this is safe to run on shared CI machines.
+pub fn prune_docker(docker_executable: &Path) -> Result<(), EvmBenchError> {
+    log::info!("pruning evm-bench containers and dangling solc images...");
+    let prune = || -> Result<(), Box<dyn error::Error>> {
+        if !docker_succeeds(
+            docker_executable,
+            &[
+                "container",
+                "prune",
+                "--force",
+                "--filter",
+                &format!("label={DOCKER_LABEL}"),
+            ],
+        )? {
+            return Err("could not prune evm-bench containers".into());
+        }
+
+        let out = Command::new(docker_executable)
+            .args([
+                "images",
+                "--quiet",
+                "--filter",
+                "dangling=true",
+                SOLC_REPOSITORY,
+            ])
+            .output()?;
+        if !out.status.success() {
+            return Err("could not list dangling solc images".into());
+        }
+        let image_ids = String::from_utf8(out.stdout)?
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<BTreeSet<_>>();
+        if image_ids.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec!["rmi"];
+        args.extend(image_ids.iter().map(String::as_str));
+        if !docker_succeeds(docker_executable, &args)? {
+            // An image still used by a container is left alone, which is not worth failing over.
+            log::warn!("could not remove every dangling solc image");
+        }
+        log::info!("removed {} dangling solc images", image_ids.len());
+        Ok(())
+    };
+    prune().map_err(EvmBenchError::Docker)
+}
+
+/// Pulls `image`, logging Docker's progress as it goes so that a slow pull does not look like a
+/// hang.
+fn docker_pull(docker_executable: &Path, image: &str) -> Result<bool, Box<dyn error::Error>> {
+    let mut child = Command::new(docker_executable)
+        .args(["pull", image])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    for line in BufReader::new(child.stdout.take().ok_or("could not read docker output")?).lines() {
+        log::info!("{image}: {}", line?);
+    }
+    let out = child.wait_with_output()?;
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    Ok(out.status.success())
+}
+
+pub(crate) fn docker_succeeds(
+    docker_executable: &Path,
+    args: &[&str],
+) -> Result<bool, Box<dyn error::Error>> {
+    let out = Command::new(docker_executable).args(args).output()?;
+    log::trace!("stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    Ok(out.status.success())
+}
+
+/// When to pull Docker images, of `solc` or of runners.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum PullPolicy {
+    /// Always pull, to pick up changes to moving tags like `stable`
+    Always,
+    /// Pull only images that are not available locally (or in the solc cache)
+    #[default]
+    Missing,
+    /// Never pull, failing if an image is not available locally (or in the solc cache)
+    Never,
+}
+
+/// Makes sure the image of every runner that runs one is available locally before anything is
+/// run, pulling it according to `pull_policy`.
+pub fn prepare_runner_images(
+    runners: &[Runner],
+    docker_executable: &Path,
+    pull_policy: PullPolicy,
+) -> Result<(), EvmBenchError> {
+    let images = runners
+        .iter()
+        .filter_map(|r| r.image.clone())
+        .collect::<BTreeSet<_>>();
+    if images.is_empty() {
+        return Ok(());
+    }
+    log::info!("preparing {} runner images...", images.len());
+
+    let prepare = |image: &str| -> Result<(), Box<dyn error::Error>> {
+        if pull_policy != PullPolicy::Always
+            && docker_succeeds(docker_executable, &["image", "inspect", image])?
+        {
+            log::debug!("found {image} locally");
+        } else if pull_policy == PullPolicy::Never {
+            return Err(
+                format!("{image} is not available locally, and pulling is disabled").into(),
+            );
+        } else {
+            log::info!("pulling {image}...");
+            if !docker_pull(docker_executable, image)? {
+                return Err(format!("could not pull {image}").into());
+            }
+        }
+        Ok(())
+    };
+    for image in images {
+        prepare(&image).map_err(EvmBenchError::Docker)?;
+    }
+
+    log::debug!("prepared all runner images");
+    Ok(())
+}
+
+/// Makes sure the `solc` image for every Solidity benchmark is available locally before any
+/// benchmark is built, so that a missing compiler is reported up front instead of mid-build.
+///
+/// Images missing from Docker are loaded from `solc_cache` if they were saved there, and are
+/// otherwise pulled (and then saved to `solc_cache`, if given, for use on offline machines).
+/// `pull_policy` can instead have every image pulled, or none.
+pub fn prepare_solc_images(
+    benchmarks: &[Benchmark],
+    docker_executable: &Path,
+    solc_cache: Option<&Path>,
+    pull_policy: PullPolicy,
+) -> Result<(), EvmBenchError> {
+    let solc_versions = benchmarks
+        .iter()
+        .filter(|b| !b.is_vyper())
+        .map(|b| b.solc_version.clone())
+        .collect::<BTreeSet<_>>();
+    log::info!(
+        "preparing {} solc versions: {}...",
+        solc_versions.len(),
+        solc_versions.iter().cloned().collect::<Vec<_>>().join(", ")
+    );
+    if let Some(solc_cache) = solc_cache {
+        create_dir_all(solc_cache).map_err(|e| EvmBenchError::Docker(e.into()))?;
+    }
+
+    for solc_version in solc_versions {
+        prepare_solc_image(&solc_version, docker_executable, solc_cache, pull_policy)
+            .map_err(EvmBenchError::Docker)?;
+    }
+
+    log::debug!("prepared all solc versions");
+    Ok(())
+}
+
+fn prepare_solc_image(
+    solc_version: &str,
+    docker_executable: &Path,
+    solc_cache: Option<&Path>,
+    pull_policy: PullPolicy,
+) -> Result<(), Box<dyn error::Error>> {
+    let image = solc_image(solc_version);
+    let cached_image_path =
+        solc_cache.map(|solc_cache| solc_cache.join(format!("solc-{solc_version}.tar")));
+
+    if pull_policy != PullPolicy::Always
+        && docker_succeeds(docker_executable, &["image", "inspect", &image])?
+    {
+        log::debug!("found {image} locally");
+    } else if let Some(cached_image_path) = cached_image_path
+        .as_ref()
+        .filter(|p| pull_policy != PullPolicy::Always && p.is_file())
+    {
+        log::info!("loading {image} from {}...", cached_image_path.display());
+        if !docker_succeeds(
+            docker_executable,
+            &["load", "-i", &cached_image_path.to_string_lossy()],
+        )? {
+            return Err(format!(
+                "could not load {image} from {}",
+                cached_image_path.display()
+            )
+            .into());
+        }
+    } else if pull_policy == PullPolicy::Never {
+        return Err(format!(
+            "{image} is not available locally or in a solc cache directory, and pulling is disabled"
+        )
+        .into());
     } else {
-        Err(format!("{}", out.status).into())
+        log::info!("pulling {image}...");
+        if !docker_pull(docker_executable, &image)? {
+            return Err(format!(
+                "could not pull {image}, if this machine is offline then provide the image \
+                 in a solc cache directory"
+            )
+            .into());
+        }
+        if let Some(cached_image_path) = cached_image_path {
+            log::info!("saving {image} to {}...", cached_image_path.display());
+            if !docker_succeeds(
+                docker_executable,
+                &["save", "-o", &cached_image_path.to_string_lossy(), &image],
+            )? {
+                log::warn!("could not save {image} to {}", cached_image_path.display());
+            }
+        }
     }
+
+    let out = Command::new(docker_executable)
+        .args(["run", "--rm", "--network", "none", &image, "--version"])
+        .output()?;
+    let version = String::from_utf8_lossy(&out.stdout);
+    log::debug!(
+        "solc@{solc_version} resolves to {}",
+        version.lines().last().unwrap_or_default().trim()
+    );
+    Ok(())
+}
+
+/// Builds a single benchmark, like [`build_benchmarks`] but without building any others, for
+/// quick checks like validating a new benchmark. Its `solc` image is pulled if missing.
+pub fn build_single_benchmark(
+    benchmark: &Benchmark,
+    solc: &Solc,
+    builds_path: &Path,
+    allow_network: bool,
+    vyper_executable: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<BuiltBenchmark, EvmBenchError> {
+    let start = Instant::now();
+    let mut built = build_benchmark(
+        benchmark,
+        &BuildContext {
+            solc: solc.clone(),
+            contract_path: benchmark.contract.clone(),
+            contract_context_path: benchmark.build_context.clone(),
+            build_path: builds_path.join(&benchmark.name),
+            allow_network,
+            vyper_executable: vyper_executable.map(Path::to_path_buf),
+            timeout,
+        },
+    )?;
+    built.result.build_time = start.elapsed();
+    Ok(built)
 }
 
+/// Builds every benchmark: Solidity contracts with `solc`, each in their own container unless a
+/// native `solc` is given, and Vyper contracts (those with a `.vy` extension) with the given
+/// `vyper` executable.
+///
+/// Build containers have no network access unless `allow_network` is set, since compiling a
+/// self-contained contract should never need it. Pulling the `solc` image itself is done by the
+/// Docker daemon and is unaffected.
+///
+/// A build that takes longer than `timeout` is killed and recorded as a failure.
 pub fn build_benchmarks(
-    benchmarks: &Vec<Benchmark>,
-    docker_executable: &Path,
+    benchmarks: &[Benchmark],
+    solc: &Solc,
     builds_path: &Path,
-) -> Result<Vec<BuiltBenchmark>, Box<dyn error::Error>> {
+    allow_network: bool,
+    vyper_executable: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<BuiltBenchmarks, EvmBenchError> {
+    build_benchmarks_with_progress(
+        benchmarks,
+        solc,
+        builds_path,
+        allow_network,
+        vyper_executable,
+        timeout,
+        &mut |_| {},
+    )
+}
+
+/// Builds every benchmark like [`build_benchmarks`], calling `on_progress` as each build starts
+/// and finishes.
+pub fn build_benchmarks_with_progress(
+    benchmarks: &[Benchmark],
+    solc: &Solc,
+    builds_path: &Path,
+    allow_network: bool,
+    vyper_executable: Option<&Path>,
+    timeout: Option<Duration>,
+    on_progress: &mut dyn FnMut(Progress<&Benchmark>),
+) -> Result<BuiltBenchmarks, EvmBenchError> {
     let benchmark_names = benchmarks
         .iter()
         .map(|b| b.name.clone())
@@ -121,31 +728,184 @@ pub fn build_benchmarks(
             .join(", ")
     );
 
-    let mut results = Vec::<BuiltBenchmark>::new();
-    for benchmark in benchmarks {
-        results.push(
-            match build_benchmark(
-                benchmark,
-                &BuildContext {
-                    docker_executable: docker_executable.to_path_buf(),
-                    contract_path: benchmark.contract.clone(),
-                    contract_context_path: benchmark.build_context.clone(),
-                    build_path: builds_path.join(&benchmark.name),
-                },
-            ) {
-                Ok(res) => res,
-                Err(e) => {
-                    log::warn!("could not build benchmark {}: {e}", benchmark.name);
-                    continue;
-                }
-            },
-        );
+    let mut results = BuiltBenchmarks::default();
+    for (done, benchmark) in benchmarks.iter().enumerate() {
+        if interrupted() {
+            log::warn!("interrupted, not building any more benchmarks");
+            break;
+        }
+        let progress = |state, done| Progress {
+            item: benchmark,
+            state,
+            done,
+            total: benchmarks.len(),
+        };
+        on_progress(progress(ProgressState::Started, done));
+        match build_single_benchmark(
+            benchmark,
+            solc,
+            builds_path,
+            allow_network,
+            vyper_executable,
+            timeout,
+        ) {
+            Ok(res) => {
+                results.built.extend(expand_calldata_sweep(res)?);
+                on_progress(progress(ProgressState::Succeeded, done + 1));
+            }
+            Err(e) => {
+                log::warn!("could not build benchmark {}: {e}", benchmark.name);
+                results.failed.push(BuildFailure {
+                    name: benchmark.name.clone(),
+                    reason: e.to_string(),
+                });
+                on_progress(progress(ProgressState::Failed, done + 1));
+            }
+        }
     }
 
     log::debug!(
         "built {} benchmarks ({} successful)",
         benchmarks.len(),
-        results.len()
+        results.built.len()
     );
     Ok(results)
 }
+
+/// Expands a built benchmark with a calldata sweep into one built benchmark per size, all sharing
+/// the one build. See [`Benchmark::expand_calldata_sweep`].
+fn expand_calldata_sweep(built: BuiltBenchmark) -> Result<Vec<BuiltBenchmark>, EvmBenchError> {
+    if built.benchmark.calldata_sweep.is_none() {
+        return Ok(vec![built]);
+    }
+    built
+        .benchmark
+        .expand_calldata_sweep()
+        .into_iter()
+        .map(|benchmark| {
+            Ok(BuiltBenchmark {
+                result: BuildResult {
+                    bytecode_hash: bytecode_hash(
+                        &built.result.contract_bin_path,
+                        &benchmark.calldata,
+                    )
+                    .map_err(EvmBenchError::Compile)?,
+                    ..built.result.clone()
+                },
+                benchmark,
+            })
+        })
+        .collect()
+}
+
+/// A built benchmark in a form that can be run on another machine: with its bytecode itself
+/// rather than a path to it.
+#[derive(Deserialize, Serialize)]
+struct SavedBenchmark {
+    benchmark: Benchmark,
+    bytecode: String,
+    warnings: Vec<String>,
+}
+
+/// Saves `benchmarks` to `path` as JSON, bytecode and all, so that they can be run later,
+/// possibly elsewhere, without building them again. See [`load_built_benchmarks`].
+pub fn save_built_benchmarks(
+    benchmarks: &[BuiltBenchmark],
+    path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let saved = benchmarks
+        .iter()
+        .map(|b| {
+            Ok(SavedBenchmark {
+                benchmark: b.benchmark.clone(),
+                bytecode: fs::read_to_string(&b.result.contract_bin_path)?,
+                warnings: b.result.warnings.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn error::Error>>>()?;
+    fs::write(path, serde_json::to_string_pretty(&saved)?)?;
+    log::info!(
+        "saved {} built benchmarks to {}",
+        saved.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Loads benchmarks saved with [`save_built_benchmarks`] from `path`, writing their bytecode out
+/// under `builds_path` for runners to read.
+pub fn load_built_benchmarks(
+    path: &Path,
+    builds_path: &Path,
+) -> Result<Vec<BuiltBenchmark>, Box<dyn error::Error>> {
+    let saved = serde_json::from_str::<Vec<SavedBenchmark>>(&fs::read_to_string(path)?)?;
+    let benchmarks = saved
+        .into_iter()
+        .map(|saved| {
+            let build_path = builds_path.join(&saved.benchmark.name);
+            create_dir_all(&build_path)?;
+            let mut contract_bin_path = build_path.join(
+                saved
+                    .benchmark
+                    .contract
+                    .file_name()
+                    .ok_or("could not get contract file name")?,
+            );
+            contract_bin_path.set_extension("bin");
+            fs::write(&contract_bin_path, &saved.bytecode)?;
+            Ok(BuiltBenchmark {
+                result: BuildResult {
+                    bytecode_hash: bytecode_hash(&contract_bin_path, &saved.benchmark.calldata)?,
+                    contract_bin_path,
+                    warnings: saved.warnings,
+                    build_time: Duration::ZERO,
+                },
+                benchmark: saved.benchmark,
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn error::Error>>>()?;
+    log::info!(
+        "loaded {} built benchmarks from {}",
+        benchmarks.len(),
+        path.display()
+    );
+    Ok(benchmarks)
+}
+
+/// Drops every benchmark with the same bytecode, calldata, mode, execution context, initial
+/// state, and setup calldata as an earlier one, since running it would only measure the same
+/// thing again.
+pub fn dedupe_benchmarks(benchmarks: &mut Vec<BuiltBenchmark>) {
+    let mut seen = HashMap::<
+        (
+            String,
+            BenchmarkMode,
+            EvmConfig,
+            Option<InitialState>,
+            Option<Vec<u8>>,
+        ),
+        String,
+    >::new();
+    benchmarks.retain(|b| {
+        let key = (
+            b.result.bytecode_hash.clone(),
+            b.benchmark.mode,
+            b.benchmark.evm_config.clone(),
+            b.benchmark.initial_state.clone(),
+            b.benchmark.setup_calldata.clone(),
+        );
+        match seen.get(&key) {
+            Some(name) => {
+                log::info!(
+                    "skipping benchmark {}, it is identical to benchmark {name}",
+                    b.benchmark.name
+                );
+                false
+            }
+            None => {
+                seen.insert(key, b.benchmark.name.clone());
+                true
+            }
+        }
+    });
+}
@@ -0,0 +1,23 @@
+use std::error;
+
+use thiserror::Error;
+
+/// The kinds of failure evm-bench can run into, so that callers can tell them apart.
+#[derive(Debug, Error)]
+pub enum EvmBenchError {
+    /// Benchmark or runner metadata could not be found or parsed.
+    #[error("could not parse metadata: {0}")]
+    MetadataParse(Box<dyn error::Error>),
+    /// Docker could not prepare a compiler image.
+    #[error("docker failed: {0}")]
+    Docker(Box<dyn error::Error>),
+    /// A benchmark contract could not be compiled.
+    #[error("could not compile benchmark: {0}")]
+    Compile(Box<dyn error::Error>),
+    /// A runner could not be started, exited unsuccessfully, or timed out.
+    #[error("runner failed: {0}")]
+    Run(Box<dyn error::Error>),
+    /// A runner's output could not be parsed.
+    #[error("could not parse runner output: {0}")]
+    OutputParse(Box<dyn error::Error>),
+}
@@ -1,15 +1,54 @@
 use std::{
     error,
+    io::{self, Read},
+    mem::MaybeUninit,
+    os::unix::process::{CommandExt, ExitStatusExt},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// How often a running process is checked on for interrupts.
+const INTERRUPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+extern "C" fn handle_interrupt(_: libc::c_int) {
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        // SAFETY: `_exit` is async-signal-safe.
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Installs a SIGINT handler that, rather than exiting, makes [`interrupted`] return true and
+/// kills any process started by [`output_with_peak_memory`], so that work can be wound down
+/// cleanly. A second SIGINT exits immediately.
+pub fn install_interrupt_handler() {
+    // SAFETY: the handler only touches an atomic and calls async-signal-safe functions.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handle_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        )
+    };
+}
+
+/// Whether SIGINT was received since [`install_interrupt_handler`] was called.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
 pub fn validate_executable(
     name: &str,
     executable: &Path,
 ) -> Result<PathBuf, Box<dyn error::Error>> {
     log::trace!("validating executable {} ({name})", executable.display());
-    match Command::new(&executable).arg("--version").output() {
+    match Command::new(executable).arg("--version").output() {
         Ok(out) => {
             log::debug!(
                 "found {name} ({}): {}",
@@ -28,3 +67,120 @@ pub fn validate_executable(
         },
     }
 }
+
+/// Runs `command` to completion like [`Command::output`], additionally reporting the peak
+/// resident set size of the process (and any descendants it waited on) in bytes.
+///
+/// The peak is read from the kernel's resource usage accounting once the process exits, so
+/// nothing is sampled while the process is running.
+///
+/// If `timeout` elapses, or SIGINT is received, before the process exits, the process and
+/// everything in its process group are killed and an error is returned.
+pub fn output_with_peak_memory(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> Result<(Output, Option<u64>), Box<dyn error::Error>> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()?;
+    let pid = child.id() as libc::pid_t;
+
+    // The watchdog only ever signals the process group while `exited` is false, which is
+    // flipped before the process is reaped so that its pid can never have been reused.
+    let exited = Arc::new(Mutex::new(false));
+    let killed_because = Arc::new(Mutex::new(None));
+    let (exit_sender, exit_receiver) = mpsc::channel::<()>();
+    let watchdog = {
+        let exited = exited.clone();
+        let killed_because = killed_because.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let reason = loop {
+                if let Some(timeout) = timeout {
+                    if start.elapsed() >= timeout {
+                        break format!("timed out after {timeout:?}");
+                    }
+                }
+                if interrupted() {
+                    break "interrupted".to_string();
+                }
+                if let Err(mpsc::RecvTimeoutError::Disconnected) =
+                    exit_receiver.recv_timeout(INTERRUPT_POLL_INTERVAL)
+                {
+                    return;
+                }
+            };
+            let exited = exited.lock().unwrap();
+            if !*exited {
+                log::warn!("process {pid} {reason}, killing it...");
+                *killed_because.lock().unwrap() = Some(reason);
+                // SAFETY: `pid` has not been reaped yet, so it still names our child's group.
+                unsafe { libc::kill(-pid, libc::SIGKILL) };
+            }
+        })
+    };
+
+    let mut stdout_pipe = child.stdout.take().ok_or("could not capture stdout")?;
+    let mut stderr_pipe = child.stderr.take().ok_or("could not capture stderr")?;
+    let stderr_reader = thread::spawn(move || -> io::Result<Vec<u8>> {
+        let mut stderr = Vec::new();
+        stderr_pipe.read_to_end(&mut stderr)?;
+        Ok(stderr)
+    });
+    let mut stdout = Vec::new();
+    stdout_pipe.read_to_end(&mut stdout)?;
+    let stderr = stderr_reader
+        .join()
+        .map_err(|_| "could not read stderr")??;
+
+    let mut info = MaybeUninit::<libc::siginfo_t>::zeroed();
+    // SAFETY: `info` points to writable memory of the correct type, and `WNOWAIT` leaves the
+    // child waitable so that it is reaped below.
+    let ret = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            info.as_mut_ptr(),
+            libc::WEXITED | libc::WNOWAIT,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    *exited.lock().unwrap() = true;
+    drop(exit_sender);
+    watchdog.join().map_err(|_| "could not join watchdog")?;
+
+    let mut status = 0;
+    let mut rusage = MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: `child` has exited but has not been reaped yet, so its pid is still ours to
+    // reap, and `rusage` points to writable memory of the correct type.
+    let ret = unsafe { libc::wait4(pid, &mut status, 0, rusage.as_mut_ptr()) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    if let Some(reason) = killed_because.lock().unwrap().take() {
+        return Err(reason.into());
+    }
+    // SAFETY: `wait4` succeeded and so filled in `rusage`.
+    let rusage = unsafe { rusage.assume_init() };
+
+    // `ru_maxrss` is reported in kilobytes on Linux but in bytes on macOS.
+    let max_rss = u64::try_from(rusage.ru_maxrss).ok();
+    let peak_memory_bytes = if cfg!(target_os = "macos") {
+        max_rss
+    } else {
+        max_rss.map(|kb| kb * 1024)
+    };
+
+    Ok((
+        Output {
+            status: ExitStatus::from_raw(status),
+            stdout,
+            stderr,
+        },
+        peak_memory_bytes.filter(|bytes| *bytes > 0),
+    ))
+}
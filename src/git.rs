@@ -0,0 +1,116 @@
+use std::{
+    error, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A reference to a directory within a remote git repository.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct GitSource {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub reference: String,
+    pub subdirectory: Option<PathBuf>,
+}
+
+fn git(args: &[&str], dir: Option<&Path>) -> Result<String, Box<dyn error::Error>> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let out = command.args(args).output()?;
+    log::trace!("stdout: {}", String::from_utf8_lossy(&out.stdout));
+    log::trace!("stderr: {}", String::from_utf8_lossy(&out.stderr));
+    if out.status.success() {
+        Ok(String::from_utf8(out.stdout)?)
+    } else {
+        Err(format!("git {} failed: {}", args.join(" "), out.status).into())
+    }
+}
+
+fn is_commit_hash(reference: &str) -> bool {
+    reference.len() == 40 && reference.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn resolve_commit(source: &GitSource) -> Result<String, Box<dyn error::Error>> {
+    if is_commit_hash(&source.reference) {
+        return Ok(source.reference.clone());
+    }
+    let out = git(&["ls-remote", &source.url, &source.reference], None)?;
+    Ok(out
+        .split_whitespace()
+        .next()
+        .ok_or(format!(
+            "could not find ref {} in {}",
+            source.reference, source.url
+        ))?
+        .to_string())
+}
+
+/// Checks out `source` under `cache_path` and returns the path to its subdirectory.
+///
+/// Checkouts are cached by commit, so a source whose ref still points to the same commit is not
+/// fetched again.
+pub fn checkout(source: &GitSource, cache_path: &Path) -> Result<PathBuf, Box<dyn error::Error>> {
+    let commit = resolve_commit(source)?;
+    let checkout_path = cache_path.join(&commit);
+
+    if checkout_path.is_dir() {
+        log::debug!(
+            "found cached checkout of {}@{} ({commit})",
+            source.url,
+            source.reference
+        );
+    } else {
+        log::info!(
+            "checking out {}@{} ({commit})...",
+            source.url,
+            source.reference
+        );
+        fs::create_dir_all(cache_path)?;
+        let partial_checkout_path = cache_path.join(format!("{commit}.partial"));
+        if partial_checkout_path.exists() {
+            fs::remove_dir_all(&partial_checkout_path)?;
+        }
+        git(
+            &[
+                "clone",
+                "--quiet",
+                &source.url,
+                &partial_checkout_path.to_string_lossy(),
+            ],
+            None,
+        )?;
+        git(
+            &["checkout", "--quiet", "--detach", &commit],
+            Some(&partial_checkout_path),
+        )?;
+        fs::rename(&partial_checkout_path, &checkout_path)?;
+    }
+
+    Ok(match &source.subdirectory {
+        Some(subdirectory) => checkout_path.join(subdirectory),
+        None => checkout_path,
+    })
+}
+
+/// Lists the files under `path` that differ between `base_ref` and the working tree, as absolute
+/// paths.
+pub fn changed_files(base_ref: &str, path: &Path) -> Result<Vec<PathBuf>, Box<dyn error::Error>> {
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"], Some(path))?.trim());
+    Ok(git(
+        &[
+            "diff",
+            "--name-only",
+            base_ref,
+            "--",
+            &path.to_string_lossy(),
+        ],
+        Some(path),
+    )?
+    .lines()
+    .map(|line| root.join(line))
+    .collect())
+}
@@ -0,0 +1,90 @@
+use std::{error, fs};
+
+use serde::{Deserialize, Serialize};
+
+use crate::metadata::{Runner, RunnerRequirements};
+
+/// A runner that was not used, because the host does not meet its requirements or because it
+/// failed its health check.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SkippedRunner {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Total physical memory of the host in bytes, if it can be determined.
+fn total_memory_bytes() -> Option<u64> {
+    // SAFETY: `sysconf` has no preconditions.
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    Some(u64::try_from(pages).ok()? * u64::try_from(page_size).ok()?)
+}
+
+/// CPU feature flags of the host, as listed in `/proc/cpuinfo`.
+fn cpu_flags() -> Result<Vec<String>, Box<dyn error::Error>> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
+    let flags = cpuinfo
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            matches!(key.trim(), "flags" | "Features").then_some(value)
+        })
+        .ok_or("could not find cpu flags in /proc/cpuinfo")?;
+    Ok(flags.split_whitespace().map(str::to_lowercase).collect())
+}
+
+/// Why the host does not meet `requirements`, or `None` if it does. Requirements that cannot
+/// be checked on this host are assumed to be met.
+fn unmet_requirement(requirements: &RunnerRequirements) -> Option<String> {
+    if let Some(min_memory_bytes) = requirements.min_memory_bytes {
+        match total_memory_bytes() {
+            Some(memory_bytes) if memory_bytes < min_memory_bytes => {
+                return Some(format!(
+                    "host has {memory_bytes} bytes of memory, but {min_memory_bytes} are required"
+                ))
+            }
+            Some(_) => {}
+            None => log::warn!("could not determine host memory, assuming there is enough"),
+        }
+    }
+
+    if !requirements.cpu_flags.is_empty() {
+        match cpu_flags() {
+            Ok(flags) => {
+                if let Some(flag) = requirements
+                    .cpu_flags
+                    .iter()
+                    .find(|flag| !flags.contains(&flag.to_lowercase()))
+                {
+                    return Some(format!("host lacks {}", flag.to_uppercase()));
+                }
+            }
+            Err(e) => log::warn!("could not determine host cpu flags, assuming they are met: {e}"),
+        }
+    }
+
+    None
+}
+
+/// Splits `runners` into those the host meets the requirements of, and those it does not.
+pub fn check_runner_requirements(runners: Vec<Runner>) -> (Vec<Runner>, Vec<SkippedRunner>) {
+    let mut usable = Vec::new();
+    let mut skipped = Vec::new();
+    for runner in runners {
+        match unmet_requirement(&runner.requirements) {
+            Some(reason) => {
+                log::warn!("skipping runner {}: {reason}", runner.name);
+                skipped.push(SkippedRunner {
+                    name: runner.name,
+                    reason,
+                });
+            }
+            None => usable.push(runner),
+        }
+    }
+    (usable, skipped)
+}
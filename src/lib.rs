@@ -0,0 +1,19 @@
+//! Ethereum Virtual Machine Benchmark (evm-bench)
+//!
+//! The `evm-bench` binary is a thin CLI over this library: benchmarks and runners are found
+//! with [`metadata`], benchmarks are compiled with [`build`], run on every runner with
+//! [`run`], and the results are recorded and printed with [`results`]. Run times are summarized
+//! with [`stats`], and failures along the way are described by [`error::EvmBenchError`].
+//! Embedders can follow the build and run phases with [`progress`] hooks, or only list what a
+//! suite has with [`metadata::discover`].
+
+pub mod build;
+pub mod error;
+pub mod exec;
+pub mod git;
+pub mod host;
+pub mod metadata;
+pub mod progress;
+pub mod results;
+pub mod run;
+pub mod stats;
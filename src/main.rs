@@ -1,56 +1,207 @@
-use std::{error, fs, path::PathBuf, process::exit};
+use std::{
+    error, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::exit,
+    time::{Duration, Instant},
+};
 
-extern crate glob;
+use chrono::Utc;
+use clap::{Parser, Subcommand, ValueEnum};
+use thiserror::Error;
+
+use evm_bench::{
+    build::{
+        build_benchmarks, dedupe_benchmarks, load_built_benchmarks, prepare_runner_images,
+        prepare_solc_images, prune_docker, save_built_benchmarks, BuiltBenchmarks, PullPolicy,
+        Solc,
+    },
+    exec::{install_interrupt_handler, interrupted, validate_executable},
+    git::changed_files,
+    host::check_runner_requirements,
+    metadata::{
+        find_benchmarks, find_runners, BenchmarkDefaults, CostRunCounts, NameFilter, Runner,
+        RunnerDefaults, RunnerOption, BENCHMARK_METADATA_NAME, RUNNER_METADATA_NAME,
+    },
+    results::{
+        archive_inputs, baseline_results_file_path, compress_results, latest_results_file_path,
+        print_comparison, print_results, print_runner_comparison, print_table_json, record_results,
+        render_results_table, results_incomplete, save_baseline, verify_consistency, write_badges,
+        write_manifest, Compression, ExitSummary, OutputTimezone, ResultsFileNaming, TimeFormat,
+        TimeUnit, Timings,
+    },
+    run::{
+        check_runner_health, preflight_benchmarks, run_benchmarks_on_runners_with_config,
+        run_bytecode, warm_runner_images, RunConfig, RunSchedule,
+    },
+    stats,
+};
 
-use clap::Parser;
-use results::{print_results, record_results};
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// Markdown tables printed to stdout
+    Table,
+    /// A shields.io endpoint badge per benchmark, written to `badges` in the output path
+    Badges,
+    /// The numbers behind the table of run times, as JSON printed to stdout
+    TableJson,
+}
 
-mod build;
-mod exec;
-mod metadata;
-mod results;
-mod run;
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LogFormat {
+    /// Human-readable log lines
+    Human,
+    /// One JSON object per log line, with level, target, message, and timestamp fields
+    Json,
+}
 
-use crate::{
-    build::build_benchmarks,
-    exec::validate_executable,
-    metadata::{find_benchmarks, find_runners, BenchmarkDefaults},
-    run::run_benchmarks_on_runners,
-};
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render the table of run times of recorded results into a file, e.g. the README
+    Render {
+        /// Results file to render, or a results directory to render the latest results in
+        #[arg(long, default_value = "./outputs/results")]
+        from: PathBuf,
+
+        /// File to render into, between the first two occurrences of the marker
+        #[arg(long, default_value = "./README.md")]
+        to: PathBuf,
+
+        /// Marker that the table is rendered between
+        #[arg(long, default_value = "<!-- results -->")]
+        marker: String,
+    },
+    /// Run raw init code on a single runner as a one-off benchmark and print its run times,
+    /// without any benchmark metadata
+    RunBytecode {
+        /// Hex init code of the contract to deploy and call
+        #[arg(long)]
+        code: String,
+
+        /// Hex calldata to call the contract with
+        #[arg(long, default_value = "")]
+        calldata: String,
+
+        /// Name of the runner to run on
+        #[arg(long)]
+        runner: String,
+
+        /// Number of times to call the contract
+        #[arg(long, default_value = "10")]
+        num_runs: u64,
+    },
+}
 
 /// Ethereum Virtual Machine Benchmark (evm-bench)
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to use as the base for benchmarks searching
     #[arg(long, default_value = "./benchmarks")]
     benchmark_search_path: PathBuf,
 
-    /// Names of benchmarks to run.
+    /// Names of benchmarks to run, which may be globs like `erc20*`.
     #[arg(long, default_value = None)]
     benchmarks: Option<Vec<String>>,
 
+    /// File listing names or globs of benchmarks to run, as a JSON list or one per line, in
+    /// addition to those given with `--benchmarks`. Names prefixed with `!` are not run.
+    #[arg(long, default_value = None)]
+    benchmark_set: Option<PathBuf>,
+
+    /// Only run benchmarks with sources that changed since this git ref
+    #[arg(long, default_value = None)]
+    only_changed: Option<String>,
+
+    /// Run the built benchmarks saved to this file with --save-benchmarks-json, e.g. on another
+    /// machine, instead of finding and building benchmarks
+    #[arg(long, default_value = None, conflicts_with = "only_changed")]
+    benchmarks_from_json: Option<PathBuf>,
+
+    /// Save the built benchmarks to this file, to run them later with --benchmarks-from-json
+    #[arg(long, default_value = None, conflicts_with = "benchmarks_from_json")]
+    save_benchmarks_json: Option<PathBuf>,
+
     /// Path to use as the base for runners searching
     #[arg(short, long, default_value = "./runners")]
     runner_search_path: PathBuf,
 
-    /// Names of runners to use.
+    /// Names of runners to use, which may be globs like `revm*`.
     #[arg(long, default_value = None)]
     runners: Option<Vec<String>>,
 
+    /// File listing names or globs of runners to use, as a JSON list or one per line, in
+    /// addition to those given with `--runners`. Names prefixed with `!` are not used.
+    #[arg(long, default_value = None)]
+    runner_set: Option<PathBuf>,
+
     /// Output path for build artifacts and other things
     #[arg(short, long, default_value = "./outputs")]
     output_path: PathBuf,
 
-    /// Name of the output file, will not overwrite.
-    /// Default means to use the current datetime.
+    /// Name of the results file, checked not to exist before anything runs, so that earlier
+    /// results are never overwritten. Default means to use the current datetime.
     #[arg(long, default_value = None)]
     output_file_name: Option<String>,
 
-    /// Path to a Docker executable (this is used for solc)
+    /// strftime-style format of the current datetime in default output file names, instead of
+    /// RFC 3339
+    #[arg(long, default_value = None, conflicts_with = "output_file_name")]
+    output_time_format: Option<String>,
+
+    /// Timezone of the current datetime in default output file names: utc, local, or an offset
+    /// like +05:30
+    #[arg(
+        long,
+        default_value = "utc",
+        allow_hyphen_values = true,
+        conflicts_with = "output_file_name"
+    )]
+    output_timezone: OutputTimezone,
+
+    /// Path to a Docker executable (this is used for solc and for runners that run an image)
     #[arg(long, default_value = "docker")]
     docker_executable: PathBuf,
 
+    /// User and group, as uid:gid, to run runner images as, the current user and group if not
+    /// given
+    #[arg(long, default_value = None)]
+    container_user: Option<String>,
+
+    /// Directory to load `solc` images from, and save pulled ones to, for offline use
+    #[arg(long, default_value = None)]
+    solc_cache: Option<PathBuf>,
+
+    /// When to pull solc and runner images
+    #[arg(long, value_enum, default_value_t = PullPolicy::Missing)]
+    pull: PullPolicy,
+
+    /// After building, remove build containers evm-bench left behind and dangling solc images,
+    /// to keep long-lived CI machines from filling up
+    #[arg(long)]
+    prune_after_build: bool,
+
+    /// Allow benchmark build containers and runner containers to access the network (they run
+    /// with none by default)
+    #[arg(long)]
+    allow_network: bool,
+
+    /// Maximum number of seconds building a single benchmark may take before it is killed
+    #[arg(long, default_value = None)]
+    build_timeout_secs: Option<u64>,
+
+    /// Path to a native solc executable to compile every Solidity benchmark with, instead of
+    /// Docker images of the solc versions benchmarks ask for
+    #[arg(long, default_value = None)]
+    solc_binary: Option<PathBuf>,
+
+    /// Path to a Vyper executable (this is used for benchmarks with `.vy` contracts)
+    #[arg(long, default_value = "vyper")]
+    vyper_executable: PathBuf,
+
     /// Path to a CPython executable (this is used for runners)
     #[arg(long, default_value = "python3")]
     cpython_executable: PathBuf,
@@ -67,16 +218,18 @@ struct Args {
     #[arg(long, default_value = "./benchmarks/schema.json")]
     benchmark_metadata_schema: PathBuf,
 
-    /// Name of benchmark metadata file to search for
-    #[arg(long, default_value = "benchmark.evm-bench.json")]
+    /// Name of benchmark metadata file to search for.
+    /// A YAML variant with a `.yaml` or `.yml` extension is also picked up.
+    #[arg(long, default_value = BENCHMARK_METADATA_NAME)]
     benchmark_metadata_name: String,
 
     /// Path to runner metadata schema
     #[arg(long, default_value = "./runners/schema.json")]
     runner_metadata_schema: PathBuf,
 
-    /// Name of benchmark metadata file to search
-    #[arg(long, default_value = "runner.evm-bench.json")]
+    /// Name of runner metadata file to search for.
+    /// A YAML variant with a `.yaml` or `.yml` extension is also picked up.
+    #[arg(long, default_value = RUNNER_METADATA_NAME)]
     runner_metadata_name: String,
 
     /// Default solc version to use if none specified in the benchmark metadata
@@ -87,80 +240,754 @@ struct Args {
     #[arg(long, default_value = "10")]
     default_num_runs: u64,
 
+    /// Number of runs for benchmarks with a cheap cost and no number of runs in their metadata
+    #[arg(long, default_value = "25")]
+    runs_cheap: u64,
+
+    /// Number of runs for benchmarks with a moderate cost and no number of runs in their metadata
+    #[arg(long, default_value = "10")]
+    runs_moderate: u64,
+
+    /// Number of runs for benchmarks with an expensive cost and no number of runs in their
+    /// metadata
+    #[arg(long, default_value = "3")]
+    runs_expensive: u64,
+
     /// Default calldata to use if none specified in the benchmark metadata
     #[arg(long, default_value = "")]
     default_calldata_str: String,
+
+    /// Number of runs to use for every benchmark, overriding the benchmark metadata
+    #[arg(long, default_value = None)]
+    num_runs_override: Option<u64>,
+
+    /// Maximum number of seconds a runner may take on a single benchmark before it is killed
+    #[arg(long, default_value = None)]
+    timeout_secs: Option<u64>,
+
+    /// Skip benchmarks with the same compiled bytecode and calldata as another benchmark
+    #[arg(long)]
+    dedupe_benchmarks: bool,
+
+    /// Ask runners to leave out any single run of a benchmark that takes longer than this many
+    /// microseconds, e.g. because of a stall
+    #[arg(long, default_value = None)]
+    max_pass_micros: Option<u64>,
+
+    /// Command to run runners through, e.g. "taskset -c 2" or "perf stat --", split on whitespace
+    #[arg(long, default_value = None)]
+    cmd_prefix: Option<String>,
+
+    /// Run a trivial benchmark on every runner before anything else, skipping runners that do
+    /// not implement the runner interface properly
+    #[arg(long)]
+    health_check: bool,
+
+    /// Do not start each runner image once before timing anything, so that the first benchmark
+    /// on each image runner may include a cold container start
+    #[arg(long)]
+    no_image_warmup: bool,
+
+    /// Run every benchmark once on a single runner before timing anything, skipping benchmarks
+    /// that fail
+    #[arg(long)]
+    preflight: bool,
+
+    /// Name of the runner to run preflight checks on, the first runner if not given
+    #[arg(long, default_value = None, requires = "preflight")]
+    preflight_runner: Option<String>,
+
+    /// Number of times to run the whole suite, combining the results of every repetition
+    #[arg(long, default_value = None)]
+    repetitions: Option<u32>,
+
+    /// Number of seconds to spend running benchmarks, skipping runs estimated to exceed it
+    #[arg(long, default_value = None)]
+    time_budget_secs: Option<u64>,
+
+    /// Number of seconds evm-bench may take in total, after which no more runs are started, the
+    /// run in progress is killed, and partial results are written before exiting with code 3
+    #[arg(long, default_value = None)]
+    max_total_duration_secs: Option<u64>,
+
+    /// Also run every benchmark for each of these comma-separated numbers of runs, e.g. 1,10,100,
+    /// and report the mean run time at each, to show warmup effects
+    #[arg(long, default_value = None, value_delimiter = ',')]
+    scaling_num_runs: Option<Vec<u64>>,
+
+    /// Order in which benchmarks are run on runners
+    #[arg(long, value_enum, default_value_t = RunSchedule::Interleaved)]
+    schedule: RunSchedule,
+
+    /// Shuffle the order in which benchmarks are run on runners, ignoring the schedule
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Seed to shuffle with, random if not given
+    #[arg(long, default_value = None, requires = "shuffle")]
+    seed: Option<u64>,
+
+    /// Pass calldata to runners in a file instead of on the command line
+    #[arg(long)]
+    calldata_file: bool,
+
+    /// Exclude the first, usually cold, run of each benchmark on each runner from statistics
+    #[arg(long)]
+    drop_first_run: bool,
+
+    /// After running, re-run every benchmark on its fastest runner and flag it if the new mean run
+    /// time is off the recorded one by more than this percentage, which points at an unstable
+    /// environment
+    #[arg(long, num_args = 0..=1, default_missing_value = "10")]
+    verify_stability: Option<f64>,
+
+    /// Use only as many runs of each benchmark as the runner that reported the fewest, so that
+    /// every runner's mean is over the same number of runs
+    #[arg(long)]
+    equalize_passes: bool,
+
+    /// Flag benchmarks whose return data differs across runs, which points at a benchmark bug
+    #[arg(long)]
+    check_determinism: bool,
+
+    /// Record how many times each benchmark call executes each opcode with the results. Only the
+    /// revm runner supports this, and other runners are run as usual with no counts recorded
+    #[arg(long)]
+    profile: bool,
+
+    /// Name of a runner to check that all other runners return the same data as
+    #[arg(long, default_value = None)]
+    reference_runner: Option<String>,
+
+    /// Archive the bytecode and calldata of every benchmark and a description of every runner
+    /// alongside the results
+    #[arg(long)]
+    archive_inputs: bool,
+
+    /// Report just these two comma-separated runners side by side, with the ratio of their run
+    /// times, instead of every runner
+    #[arg(long, default_value = None, value_delimiter = ',')]
+    compare_runners: Option<Vec<String>>,
+
+    /// Record how long building each benchmark and running each benchmark on each runner took,
+    /// including overhead outside the measured runs, alongside the results
+    #[arg(long)]
+    collect_timings: bool,
+
+    /// Write a JSON summary of how many runs were expected and succeeded, and of every failure,
+    /// to this path
+    #[arg(long, default_value = None)]
+    summary_file: Option<PathBuf>,
+
+    /// Exit with an error unless every benchmark was run on every runner
+    #[arg(long)]
+    require_complete: bool,
+
+    /// Pin these results as the baseline that later results are compared against
+    #[arg(long)]
+    save_baseline: bool,
+
+    /// Partial results file of an interrupted run to resume, only running what it is missing
+    #[arg(long, default_value = None)]
+    resume: Option<PathBuf>,
+
+    /// Only log errors, and print only the table of run times
+    #[arg(long)]
+    quiet: bool,
+
+    /// How to output the results once they are recorded
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    output_format: OutputFormat,
+
+    /// Compress the results file with this compressor, which must be installed to read the
+    /// results back
+    #[arg(long, value_enum, default_value = None)]
+    compress: Option<Compression>,
+
+    /// Unit to show run times in, in the table of run times
+    #[arg(long, value_enum, default_value_t = TimeUnit::Auto)]
+    time_unit: TimeUnit,
+
+    /// Number of decimal places to show run times with, in the table of run times
+    #[arg(long, default_value = None)]
+    precision: Option<usize>,
+
+    /// Format of log lines
+    #[arg(long, value_enum, default_value_t = LogFormat::Human)]
+    log_format: LogFormat,
 }
 
-fn main() {
-    env_logger::init();
+fn init_logger(log_format: LogFormat, quiet: bool) {
+    let mut builder = env_logger::Builder::from_default_env();
+    if quiet {
+        builder.filter_level(log::LevelFilter::Error);
+    }
+    if let LogFormat::Json = log_format {
+        builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "{}",
+                serde_json::json!({
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                })
+            )
+        });
+    }
+    builder.init();
+}
 
+/// Warns that `flag` has no effect on the `runners` that do not accept `option`.
+fn warn_unaccepted_option(runners: &[Runner], option: RunnerOption, flag: &str) {
+    let unaccepted = runners
+        .iter()
+        .filter(|runner| !runner.accepts(option))
+        .map(|runner| runner.name.as_str())
+        .collect::<Vec<_>>();
+    if !unaccepted.is_empty() {
+        log::warn!(
+            "{flag} has no effect on runners {}, which do not accept it",
+            unaccepted.join(", ")
+        );
+    }
+}
+
+/// Exit code when evm-bench stops early for exceeding `--max-total-duration-secs`.
+const DEADLINE_EXIT_CODE: i32 = 3;
+
+/// evm-bench took longer than `--max-total-duration-secs`.
+#[derive(Debug, Error)]
+#[error("exceeded the maximum total duration of {0:?}, results are incomplete")]
+struct DeadlineExceeded(Duration);
+
+fn main() {
+    let start = Instant::now();
     let args = Args::parse();
+    init_logger(args.log_format, args.quiet);
+    install_interrupt_handler();
 
+    let time_format = TimeFormat {
+        unit: args.time_unit,
+        precision: args.precision,
+    };
     (|| -> Result<(), Box<dyn error::Error>> {
-        let docker_executable = validate_executable("docker", &args.docker_executable)?;
+        if let Some(Command::Render { from, to, marker }) = &args.command {
+            let results_file_path = if from.is_dir() {
+                latest_results_file_path(from)?
+            } else {
+                from.clone()
+            };
+            return render_results_table(&results_file_path, to, marker, time_format);
+        }
+        if let Some(Command::RunBytecode {
+            code,
+            calldata,
+            runner,
+            num_runs,
+        }) = &args.command
+        {
+            let code = code.trim_start_matches("0x");
+            hex::decode(code).map_err(|e| format!("could not parse code as hex: {e}"))?;
+            let calldata = hex::decode(calldata.trim_start_matches("0x"))
+                .map_err(|e| format!("could not parse calldata as hex: {e}"))?;
+            fs::create_dir_all(&args.output_path)?;
+            let outputs_path = args.output_path.canonicalize()?;
+            let runner = find_runners(
+                &args.runner_metadata_name,
+                &args.runner_metadata_schema,
+                &args.runner_search_path.canonicalize()?,
+                RunnerDefaults {
+                    git_cache_path: outputs_path.join("git"),
+                },
+            )?
+            .into_iter()
+            .find(|r| &r.name == runner)
+            .ok_or(format!("could not find runner {runner}"))?;
+            let mut run_config = RunConfig::builder()
+                .docker_executable(args.docker_executable.clone())
+                .allow_network(args.allow_network);
+            if runner.image.is_some() {
+                prepare_runner_images(
+                    std::slice::from_ref(&runner),
+                    &validate_executable("docker", &args.docker_executable)?,
+                    args.pull,
+                )?;
+            }
+            if let Some(container_user) = &args.container_user {
+                run_config = run_config.container_user(container_user.clone());
+            }
+            if let Some(timeout_secs) = args.timeout_secs {
+                run_config = run_config.timeout(Duration::from_secs(timeout_secs));
+            }
+            if let Some(cmd_prefix) = &args.cmd_prefix {
+                run_config = run_config
+                    .command_prefix(cmd_prefix.split_whitespace().map(str::to_string).collect());
+            }
+            let result = run_bytecode(
+                "run-bytecode",
+                code,
+                &calldata,
+                *num_runs,
+                &runner,
+                &run_config.build(),
+                &outputs_path.join("build"),
+            )?;
+            for run_time in &result.run_times {
+                println!("{}", time_format.format(*run_time));
+            }
+            let summary = stats::summary(result.measured_run_times());
+            eprintln!(
+                "mean {}, median {} over {} runs",
+                time_format.format(summary.mean),
+                time_format.format(summary.median),
+                result.run_times.len()
+            );
+            return Ok(());
+        }
+
+        // Compilers are only needed to build benchmarks, not to run saved ones.
+        let solc = match &args.solc_binary {
+            Some(solc_binary) => Solc::Native(validate_executable("solc", solc_binary)?),
+            None if args.benchmarks_from_json.is_some() => {
+                Solc::Docker(args.docker_executable.clone())
+            }
+            None => Solc::Docker(validate_executable("docker", &args.docker_executable)?),
+        };
         let _ = validate_executable("cargo", &PathBuf::from("cargo"))?;
         let _ = validate_executable("poetry", &PathBuf::from("poetry"))?;
-        let _ = validate_executable("python3", &PathBuf::from(args.cpython_executable))?;
-        let _ = validate_executable("pypy3", &PathBuf::from(args.pypy_executable))?;
-        let _ = validate_executable("npm", &PathBuf::from(args.npm_executable))?;
-
-        let default_calldata = hex::decode(args.default_calldata_str.to_string())?;
+        let _ = validate_executable("python3", &args.cpython_executable)?;
+        let _ = validate_executable("pypy3", &args.pypy_executable)?;
+        let _ = validate_executable("npm", &args.npm_executable)?;
+
+        let default_calldata = hex::decode(&args.default_calldata_str)?;
+        let compare_runners = match args.compare_runners.as_deref() {
+            Some([runner_a, runner_b]) => Some((runner_a.clone(), runner_b.clone())),
+            Some(_) => return Err("--compare-runners takes exactly two runners".into()),
+            None => None,
+        };
 
         let benchmarks_path = args.benchmark_search_path.canonicalize()?;
-        let benchmarks = find_benchmarks(
-            &args.benchmark_metadata_name,
-            &args.benchmark_metadata_schema,
-            &benchmarks_path,
-            BenchmarkDefaults {
-                solc_version: args.default_solc_version,
-                num_runs: args.default_num_runs,
-                calldata: default_calldata,
-            },
-        )?;
-        let mut benchmarks = match args.benchmarks {
-            None => benchmarks,
-            Some(arg_benchmarks) => benchmarks
-                .into_iter()
-                .filter(|b| arg_benchmarks.contains(&b.name))
-                .collect(),
+        let benchmarks = match &args.benchmarks_from_json {
+            Some(_) => Vec::new(),
+            None => find_benchmarks(
+                &args.benchmark_metadata_name,
+                &args.benchmark_metadata_schema,
+                &benchmarks_path,
+                BenchmarkDefaults {
+                    solc_version: args.default_solc_version,
+                    num_runs: args.default_num_runs,
+                    calldata: default_calldata,
+                    cost_run_counts: CostRunCounts {
+                        cheap: args.runs_cheap,
+                        moderate: args.runs_moderate,
+                        expensive: args.runs_expensive,
+                    },
+                },
+            )?,
         };
+        let benchmark_filter = NameFilter::new(args.benchmarks, args.benchmark_set.as_deref())?;
+        let mut benchmarks = benchmarks
+            .into_iter()
+            .filter(|b| benchmark_filter.matches(&b.name))
+            .collect::<Vec<_>>();
+        if let Some(base_ref) = &args.only_changed {
+            match changed_files(base_ref, &benchmarks_path) {
+                Ok(changed_files) => {
+                    benchmarks.retain(|b| {
+                        let contract_dir = b.contract.parent().unwrap_or(&b.contract);
+                        changed_files
+                            .iter()
+                            .any(|f| f.starts_with(&b.build_context) || f.starts_with(contract_dir))
+                    });
+                    log::info!(
+                        "{} benchmarks changed since {base_ref}",
+                        benchmarks.len()
+                    );
+                }
+                Err(e) => log::warn!(
+                    "could not find benchmarks changed since {base_ref}, running all of them: {e}"
+                ),
+            }
+        }
         benchmarks.sort_by_key(|b| b.name.clone());
 
         let runners_path = args.runner_search_path.canonicalize()?;
+        fs::create_dir_all(&args.output_path)?;
+        let outputs_path = args.output_path.canonicalize()?;
+
         let runners = find_runners(
             &args.runner_metadata_name,
             &args.runner_metadata_schema,
             &runners_path,
-            (),
+            RunnerDefaults {
+                git_cache_path: outputs_path.join("git"),
+            },
         )?;
-        let mut runners = match args.runners {
-            None => runners,
-            Some(arg_runners) => runners
-                .into_iter()
-                .filter(|r| arg_runners.contains(&r.name))
-                .collect(),
-        };
+        let runner_filter = NameFilter::new(args.runners, args.runner_set.as_deref())?;
+        let mut runners = runners
+            .into_iter()
+            .filter(|r| runner_filter.matches(&r.name))
+            .collect::<Vec<_>>();
         runners.sort_by_key(|b| b.name.clone());
+        let (runners, mut skipped_runners) = check_runner_requirements(runners);
+        for (enabled, option, flag) in [
+            (args.calldata_file, RunnerOption::CalldataPath, "--calldata-file"),
+            (
+                args.check_determinism,
+                RunnerOption::PrintReturnDataHashes,
+                "--check-determinism",
+            ),
+            (
+                args.max_pass_micros.is_some(),
+                RunnerOption::MaxPassMicros,
+                "--max-pass-micros",
+            ),
+            (
+                args.reference_runner.is_some(),
+                RunnerOption::PrintReturnData,
+                "--reference-runner",
+            ),
+            (args.profile, RunnerOption::Profile, "--profile"),
+        ] {
+            if enabled {
+                warn_unaccepted_option(&runners, option, flag);
+            }
+        }
+        if runners.iter().any(|r| r.image.is_some()) {
+            prepare_runner_images(
+                &runners,
+                &validate_executable("docker", &args.docker_executable)?,
+                args.pull,
+            )?;
+        }
+
+        if let Some(compression) = args.compress {
+            validate_executable(compression.executable(), Path::new(compression.executable()))?;
+        }
+
+        let vyper_executable = if benchmarks.iter().any(|b| b.is_vyper()) {
+            Some(validate_executable("vyper", &args.vyper_executable)?)
+        } else {
+            None
+        };
 
-        fs::create_dir_all(&args.output_path)?;
-        let outputs_path = args.output_path.canonicalize()?;
+        if let Solc::Docker(docker_executable) = &solc {
+            prepare_solc_images(
+                &benchmarks,
+                docker_executable,
+                args.solc_cache.as_deref(),
+                args.pull,
+            )?;
+        }
 
         let builds_path = outputs_path.join("build");
         fs::create_dir_all(&builds_path)?;
-        let built_benchmarks = build_benchmarks(&benchmarks, &docker_executable, &builds_path)?;
-
-        let results = run_benchmarks_on_runners(&built_benchmarks, &runners)?;
+        let mut built_benchmarks = match &args.benchmarks_from_json {
+            Some(benchmarks_json) => {
+                let mut built = load_built_benchmarks(benchmarks_json, &builds_path)?;
+                built.retain(|b| benchmark_filter.matches(&b.benchmark.name));
+                built.sort_by_key(|b| b.benchmark.name.clone());
+                BuiltBenchmarks {
+                    built,
+                    failed: Vec::new(),
+                }
+            }
+            None => build_benchmarks(
+                &benchmarks,
+                &solc,
+                &builds_path,
+                args.allow_network,
+                vyper_executable.as_deref(),
+                args.build_timeout_secs.map(Duration::from_secs),
+            )?,
+        };
+        if args.prune_after_build {
+            if let Solc::Docker(docker_executable) = &solc {
+                prune_docker(docker_executable)?;
+            }
+        }
+        if let Some(benchmarks_json) = &args.save_benchmarks_json {
+            save_built_benchmarks(&built_benchmarks.built, benchmarks_json)?;
+        }
+
+        if args.dedupe_benchmarks {
+            dedupe_benchmarks(&mut built_benchmarks.built);
+        }
 
         let results_path = outputs_path.join("results");
         fs::create_dir_all(&results_path)?;
-        let result_file_path = record_results(&results_path, args.output_file_name, &results)?;
-        print_results(&result_file_path)?;
+        let (result_file_name, partial_results_path) = match &args.resume {
+            Some(partial_results_path) => (
+                partial_results_path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .and_then(|f| f.strip_suffix(".partial"))
+                    .ok_or(format!(
+                        "{} is not a partial results file",
+                        partial_results_path.display()
+                    ))?
+                    .to_string(),
+                partial_results_path.clone(),
+            ),
+            None => {
+                let result_file_name = match args.output_file_name {
+                    Some(output_file_name) => output_file_name,
+                    None => ResultsFileNaming {
+                        time_format: args.output_time_format.clone(),
+                        timezone: args.output_timezone,
+                    }
+                    .file_name(&Utc::now())?,
+                };
+                if results_path.join(&result_file_name).exists()
+                    || args.compress.is_some_and(|compression| {
+                        results_path
+                            .join(format!("{result_file_name}{}", compression.extension()))
+                            .exists()
+                    })
+                {
+                    return Err(format!(
+                        "results already exist at {}, refusing to overwrite them",
+                        results_path.join(&result_file_name).display()
+                    )
+                    .into());
+                }
+                let partial_results_path =
+                    results_path.join(format!("{result_file_name}.partial"));
+                if partial_results_path.exists() {
+                    return Err(format!(
+                        "partial results of an earlier run already exist at {}, resume it with --resume or delete them",
+                        partial_results_path.display()
+                    )
+                    .into());
+                }
+                (result_file_name, partial_results_path)
+            }
+        };
+
+        let mut run_config = RunConfig::builder()
+            .schedule(args.schedule)
+            .docker_executable(args.docker_executable.clone())
+            .allow_network(args.allow_network);
+        if let Some(container_user) = &args.container_user {
+            run_config = run_config.container_user(container_user.clone());
+        }
+        if let Some(num_runs) = args.num_runs_override {
+            run_config = run_config.num_runs_override(num_runs);
+        }
+        if let Some(timeout_secs) = args.timeout_secs {
+            run_config = run_config.timeout(Duration::from_secs(timeout_secs));
+        }
+        if args.shuffle {
+            run_config = run_config.shuffle_seed(args.seed.unwrap_or_else(rand::random));
+        }
+        if let Some(cmd_prefix) = &args.cmd_prefix {
+            run_config = run_config.command_prefix(
+                cmd_prefix.split_whitespace().map(str::to_string).collect(),
+            );
+        }
+        if let Some(max_pass_micros) = args.max_pass_micros {
+            run_config = run_config.max_pass_micros(max_pass_micros);
+        }
+        if let Some(repetitions) = args.repetitions {
+            run_config = run_config.repetitions(repetitions);
+        }
+        if let Some(max_total_duration_secs) = args.max_total_duration_secs {
+            run_config =
+                run_config.deadline(start + Duration::from_secs(max_total_duration_secs));
+        }
+        if let Some(scaling_num_runs) = &args.scaling_num_runs {
+            run_config = run_config.scaling_num_runs(scaling_num_runs.clone());
+        }
+        if let Some(max_divergence_percent) = args.verify_stability {
+            run_config = run_config.verify_stability(max_divergence_percent / 100.0);
+        }
+        if let Some(time_budget_secs) = args.time_budget_secs {
+            run_config = run_config.time_budget(Duration::from_secs(time_budget_secs));
+        }
+        let run_config = run_config
+            .failures_path(outputs_path.join("failures"))
+            .partial_results_path(partial_results_path.clone())
+            .resume(args.resume.is_some())
+            .capture_return_data(args.reference_runner.is_some())
+            .drop_first_run(args.drop_first_run)
+            .calldata_file(args.calldata_file)
+            .check_determinism(args.check_determinism)
+            .equalize_passes(args.equalize_passes)
+            .profile(args.profile)
+            .build();
+        let runners = if args.health_check {
+            let (healthy, unhealthy) = check_runner_health(runners, &run_config, &builds_path);
+            skipped_runners.extend(unhealthy);
+            healthy
+        } else {
+            runners
+        };
+        // Health checks already start every runner once.
+        if !args.no_image_warmup && !args.health_check {
+            warm_runner_images(&runners, &run_config, &builds_path);
+        }
+        if args.preflight {
+            let preflight_runner = match &args.preflight_runner {
+                Some(name) => runners
+                    .iter()
+                    .find(|r| &r.name == name)
+                    .ok_or(format!("could not find preflight runner {name}"))?,
+                None => runners.first().ok_or("no runners to run preflight checks on")?,
+            };
+            let (passed, failed) =
+                preflight_benchmarks(&built_benchmarks.built, preflight_runner, &run_config);
+            let passed = passed
+                .into_iter()
+                .map(|b| b.benchmark.name.clone())
+                .collect::<Vec<_>>();
+            built_benchmarks
+                .built
+                .retain(|b| passed.contains(&b.benchmark.name));
+            built_benchmarks.failed.extend(failed);
+        }
+
+        // An interrupted run records incomplete results, which its resumption replaces.
+        let existing_result_file_path = results_path.join(&result_file_name);
+        if args.resume.is_some() && existing_result_file_path.exists() {
+            if !results_incomplete(&existing_result_file_path)? {
+                return Err(format!(
+                    "results at {} are already complete",
+                    existing_result_file_path.display()
+                )
+                .into());
+            }
+            fs::remove_file(&existing_result_file_path)?;
+        }
+
+        let run_output =
+            run_benchmarks_on_runners_with_config(&built_benchmarks.built, &runners, &run_config)?;
+
+        let timings = args
+            .collect_timings
+            .then(|| Timings::new(&built_benchmarks.built, &run_output));
+        let exit_summary = ExitSummary::new(&built_benchmarks, &runners, &run_output);
+        if let Some(summary_file) = &args.summary_file {
+            exit_summary.write(summary_file)?;
+        }
+        if let Some(timings) = &timings {
+            log::info!(
+                "spent {:.2}s building benchmarks and {:.2}s running them, {:.2}s of which was outside measured runs",
+                timings.total_build_time().as_secs_f64(),
+                timings.total_run_time().as_secs_f64(),
+                timings.total_run_overhead().as_secs_f64(),
+            );
+        }
+        let result_file_path = record_results(
+            &results_path,
+            Some(result_file_name),
+            &run_output,
+            &built_benchmarks.failed,
+            &skipped_runners,
+            &run_config,
+            timings,
+        )?;
+        if run_output.incomplete {
+            log::info!(
+                "kept partial results of completed runs in {}",
+                partial_results_path.display()
+            );
+        } else if partial_results_path.exists() {
+            fs::remove_file(&partial_results_path)?;
+        }
+        write_manifest(
+            &built_benchmarks.built,
+            &runners,
+            &result_file_path.with_extension("manifest.json"),
+        )?;
+        if args.archive_inputs {
+            archive_inputs(
+                &built_benchmarks.built,
+                &runners,
+                &result_file_path.with_extension("inputs"),
+            )?;
+        }
+        let result_file_path = match args.compress {
+            Some(compression) => compress_results(&result_file_path, compression)?,
+            None => result_file_path,
+        };
+        let reported = match (args.output_format, &compare_runners) {
+            (OutputFormat::TableJson, _) => print_table_json(&result_file_path),
+            (OutputFormat::Badges, _) => {
+                write_badges(&result_file_path, &outputs_path.join("badges"))
+            }
+            (OutputFormat::Table, Some((runner_a, runner_b))) => {
+                print_runner_comparison(&result_file_path, runner_a, runner_b)
+            }
+            (OutputFormat::Table, None) => {
+                print_results(&result_file_path, args.quiet, time_format)
+            }
+        };
+        // Stopping at the deadline has its own exit code, even if there was too little to report.
+        if run_output.deadline_exceeded {
+            if let Err(e) = reported {
+                log::warn!("could not report incomplete results: {e}");
+            }
+            return Err(DeadlineExceeded(Duration::from_secs(
+                args.max_total_duration_secs.unwrap_or_default(),
+            ))
+            .into());
+        }
+        reported?;
+        if interrupted() {
+            return Err("interrupted, results are incomplete".into());
+        }
+
+        if let Some(baseline_file_path) = baseline_results_file_path(
+            &results_path,
+            &result_file_path,
+        )?
+        .filter(|_| !args.quiet)
+        {
+            println!();
+            print_comparison(&baseline_file_path, &result_file_path)?;
+        }
+        if args.save_baseline {
+            save_baseline(&results_path, &result_file_path)?;
+        }
+
+        if let Some(reference_runner) = args.reference_runner.filter(|_| !args.quiet) {
+            let inconsistencies = verify_consistency(&run_output.results, &reference_runner);
+            if !inconsistencies.is_empty() {
+                println!();
+            }
+            for inconsistency in &inconsistencies {
+                println!(
+                    "runner {} returned {} on benchmark {}, but reference runner {reference_runner} returned {}",
+                    inconsistency.runner_name,
+                    inconsistency.return_data.as_deref().unwrap_or("nothing"),
+                    inconsistency.benchmark_name,
+                    inconsistency.expected_return_data,
+                );
+            }
+            log::info!(
+                "found {} runs inconsistent with reference runner {reference_runner}",
+                inconsistencies.len()
+            );
+        }
+
+        if args.require_complete && !exit_summary.is_complete() {
+            return Err(format!(
+                "only {}/{} benchmark runs succeeded",
+                exit_summary.runs_succeeded, exit_summary.runs_expected
+            )
+            .into());
+        }
 
         Ok(())
     })()
     .unwrap_or_else(|e| {
         log::error!("{e}");
-        exit(-1);
+        exit(if e.is::<DeadlineExceeded>() {
+            DEADLINE_EXIT_CODE
+        } else {
+            -1
+        });
     });
 }
@@ -1,11 +1,23 @@
 use std::{
-    collections::HashSet,
-    error, fs,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env, error, fmt, fs,
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use glob::glob;
-use serde::{Deserialize, Serialize};
+use ethabi::{
+    param_type::Reader,
+    token::{LenientTokenizer, Tokenizer},
+};
+use glob::{glob, MatchOptions, Pattern};
+use serde::{de, Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    error::EvmBenchError,
+    git::{checkout, GitSource},
+};
 
 pub trait MetadataParser
 where
@@ -13,6 +25,10 @@ where
 {
     type Defaults;
 
+    /// Name of the files whose keys metadata files in the same directory, or any directory below
+    /// it, inherit when they do not give those keys themselves.
+    const INHERITED_FILE_NAME: Option<&'static str> = None;
+
     fn parse_schema_from_file(
         schema_path: &Path,
     ) -> Result<serde_json::Value, Box<dyn error::Error>> {
@@ -22,17 +38,39 @@ where
 
     fn parse_from_file(
         schema: &serde_json::Value,
-        json_path: &Path,
+        metadata_path: &Path,
         defaults: &Self::Defaults,
     ) -> Result<Self, Box<dyn error::Error>> {
-        let json_file = fs::File::open(json_path)?;
-        let json = serde_json::from_reader(&json_file)?;
-        Self::parse(
-            json_path.parent().ok_or("could not get parent")?,
-            schema,
-            &json,
-            defaults,
-        )
+        Self::parse_from_file_inheriting(schema, metadata_path, &serde_json::Map::new(), defaults)
+    }
+
+    /// Parses the metadata file at `metadata_path` like [`MetadataParser::parse_from_file`],
+    /// taking any key it does not give itself from `inherited`.
+    fn parse_from_file_inheriting(
+        schema: &serde_json::Value,
+        metadata_path: &Path,
+        inherited: &serde_json::Map<String, serde_json::Value>,
+        defaults: &Self::Defaults,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        (|| {
+            let metadata = fs::read_to_string(metadata_path)?;
+            let yaml = is_yaml_path(metadata_path);
+            let mut json = metadata_json_from_str(&metadata, yaml)?;
+            if let Some(object) = json.as_object_mut() {
+                for (key, value) in inherited {
+                    object.entry(key).or_insert_with(|| value.clone());
+                }
+            }
+            validate_against_schema(schema, &json, |pointer| {
+                line_of_pointer(&metadata, yaml, pointer)
+            })?;
+            Self::parse_inner(
+                metadata_path.parent().ok_or("could not get parent")?,
+                &json,
+                defaults,
+            )
+        })()
+        .map_err(|e: Box<dyn error::Error>| format!("{}: {e}", metadata_path.display()).into())
     }
 
     fn parse(
@@ -41,11 +79,8 @@ where
         json: &serde_json::Value,
         defaults: &Self::Defaults,
     ) -> Result<Self, Box<dyn error::Error>> {
-        if jsonschema::is_valid(schema, json) {
-            Self::parse_inner(base_path, json, defaults)
-        } else {
-            Err("json does not abide by the schema".into())
-        }
+        validate_against_schema(schema, json, |_| None)?;
+        Self::parse_inner(base_path, json, defaults)
     }
 
     fn parse_inner(
@@ -60,20 +95,515 @@ pub struct Benchmark {
     pub name: String,
     pub solc_version: String,
     pub num_runs: u64,
+    #[serde(default)]
+    pub cost: Option<BenchmarkCost>,
     pub contract: PathBuf,
     pub build_context: PathBuf,
     pub calldata: Vec<u8>,
+    #[serde(default)]
+    pub evm_config: EvmConfig,
+    /// Number of times runners call the contract within each timed run, to amortize timer
+    /// overhead for very fast benchmarks. Run times are recorded per call.
+    #[serde(default = "default_inner_iterations")]
+    pub inner_iterations: u64,
+    #[serde(default)]
+    pub weight: BenchmarkWeight,
+    #[serde(default)]
+    pub mode: BenchmarkMode,
+    /// How long runners call the contract for in each run of a throughput benchmark, in
+    /// milliseconds. Runners use their own default if not given.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub initial_state: Option<InitialState>,
+    /// Version of the compiler the benchmark was actually built with, which `solc_version` may
+    /// only name loosely (e.g. `stable`). Unknown until the benchmark is built.
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    /// Calldata sizes to run the benchmark at, which building expands into one benchmark per
+    /// size. See [`CalldataSweep`].
+    #[serde(default)]
+    pub calldata_sweep: Option<CalldataSweep>,
+    /// Number of bytes of sweep calldata, for a benchmark expanded from a calldata sweep.
+    #[serde(default)]
+    pub calldata_sweep_size: Option<usize>,
+    /// Calldata of an untimed call that runners commit to the contract before timing any calls,
+    /// for benchmarks of calls that depend on an earlier one, e.g. `transferFrom` after
+    /// `approve`.
+    #[serde(default)]
+    pub setup_calldata: Option<Vec<u8>>,
+}
+
+impl Benchmark {
+    /// Expands a benchmark with a calldata sweep into one benchmark per size, named
+    /// `<name>@<size>`, or returns it as is if it has none.
+    pub fn expand_calldata_sweep(&self) -> Vec<Benchmark> {
+        let Some(sweep) = &self.calldata_sweep else {
+            return vec![self.clone()];
+        };
+        sweep
+            .sizes
+            .iter()
+            .map(|&size| {
+                let mut calldata = self.calldata.clone();
+                calldata.extend(sweep.pattern.iter().cycle().take(size));
+                Benchmark {
+                    name: format!("{}@{size}", self.name),
+                    calldata,
+                    calldata_sweep: None,
+                    calldata_sweep_size: Some(size),
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Optional runner interface options that runners need to support to run the benchmark as
+    /// its metadata asks.
+    pub fn required_options(&self) -> BTreeSet<RunnerOption> {
+        let evm_config = &self.evm_config;
+        [
+            (evm_config.gas_limit.is_some(), RunnerOption::GasLimit),
+            (evm_config.chain_id.is_some(), RunnerOption::ChainId),
+            (evm_config.base_fee.is_some(), RunnerOption::BaseFee),
+            (evm_config.caller.is_some(), RunnerOption::Caller),
+            (evm_config.value.is_some(), RunnerOption::Value),
+            (self.inner_iterations > 1, RunnerOption::InnerIterations),
+            (self.mode != BenchmarkMode::Call, RunnerOption::Mode),
+            (self.duration_ms.is_some(), RunnerOption::DurationMs),
+            (self.initial_state.is_some(), RunnerOption::StatePath),
+            (self.setup_calldata.is_some(), RunnerOption::SetupCalldata),
+        ]
+        .into_iter()
+        .filter_map(|(required, option)| required.then_some(option))
+        .collect()
+    }
+}
+
+/// Calldata of increasing sizes to run a benchmark with, to see how its run time scales with
+/// calldata size, e.g. for calldata copying or hashing. Each size is run as its own benchmark,
+/// whose calldata is the benchmark's own followed by `pattern` repeated out to that many bytes.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CalldataSweep {
+    pub pattern: Vec<u8>,
+    pub sizes: Vec<usize>,
+}
+
+impl CalldataSweep {
+    fn parse(json: &serde_json::Value) -> Result<Self, Box<dyn error::Error>> {
+        let object = json
+            .as_object()
+            .ok_or("could not parse calldata sweep as object")?;
+        let pattern = hex::decode(
+            object
+                .get("pattern")
+                .map_or(Some("00"), |x| x.as_str())
+                .ok_or("could not parse calldata sweep pattern as string")?
+                .trim_start_matches("0x"),
+        )?;
+        if pattern.is_empty() {
+            return Err("calldata sweep pattern must not be empty".into());
+        }
+        let mut sizes = serde_json::from_value::<Vec<usize>>(
+            object
+                .get("sizes")
+                .ok_or("could not find calldata sweep sizes")?
+                .clone(),
+        )?;
+        sizes.sort();
+        sizes.dedup();
+        if sizes.is_empty() {
+            return Err("calldata sweep sizes must not be empty".into());
+        }
+        Ok(Self { pattern, sizes })
+    }
+}
+
+fn default_inner_iterations() -> u64 {
+    1
+}
+
+/// How much a benchmark counts towards the weighted totals in reports, relative to other
+/// benchmarks. Compared and hashed by its bits so that [`Benchmark`] can stay `Eq` and `Hash`.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct BenchmarkWeight(pub f64);
+
+impl Default for BenchmarkWeight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl PartialEq for BenchmarkWeight {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for BenchmarkWeight {}
+
+impl Hash for BenchmarkWeight {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// What runners time for a benchmark: calls to the deployed contract, its deployment, or both,
+/// or how many calls to the deployed contract they make per second.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BenchmarkMode {
+    #[default]
+    Call,
+    Deploy,
+    Both,
+    Throughput,
+}
+
+impl BenchmarkMode {
+    /// Value of the `--mode` option that asks runners to time this.
+    pub fn runner_arg(&self) -> &'static str {
+        match self {
+            BenchmarkMode::Call => "call",
+            BenchmarkMode::Deploy => "deploy",
+            BenchmarkMode::Both => "both",
+            BenchmarkMode::Throughput => "throughput",
+        }
+    }
+}
+
+/// Execution context for a benchmark, passed to runners only where the benchmark sets it so
+/// that runners keep their own defaults otherwise.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct EvmConfig {
+    pub gas_limit: Option<u64>,
+    pub chain_id: Option<u64>,
+    pub base_fee: Option<u64>,
+    /// Hex address, with a `0x` prefix, that deploys and calls the contract.
+    pub caller: Option<String>,
+    /// Wei sent along with each call, in decimal.
+    pub value: Option<String>,
+}
+
+impl EvmConfig {
+    /// Command-line arguments to pass to a runner for this configuration.
+    pub fn runner_args(&self) -> Vec<String> {
+        [
+            ("--gas-limit", self.gas_limit.map(|x| x.to_string())),
+            ("--chain-id", self.chain_id.map(|x| x.to_string())),
+            ("--base-fee", self.base_fee.map(|x| x.to_string())),
+            ("--caller", self.caller.clone()),
+            ("--value", self.value.clone()),
+        ]
+        .into_iter()
+        .flat_map(|(flag, value)| value.map(|value| [flag.to_string(), value]))
+        .flatten()
+        .collect()
+    }
+}
+
+/// Accounts and storage that runners load before running a benchmark, e.g. the balances an ERC20
+/// transfer moves. Addresses, words, and code are normalized to lowercase hex with a `0x` prefix,
+/// and balances to decimal.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct InitialState {
+    #[serde(default)]
+    pub accounts: Vec<InitialAccount>,
+    #[serde(default)]
+    pub storage: Vec<InitialStorage>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct InitialAccount {
+    pub address: String,
+    pub balance: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct InitialStorage {
+    /// Account the slot belongs to, or the benchmark contract if not given.
+    pub address: Option<String>,
+    pub slot: String,
+    pub value: String,
+}
+
+impl InitialState {
+    fn parse(json: &serde_json::Value) -> Result<Self, Box<dyn error::Error>> {
+        let object = json
+            .as_object()
+            .ok_or("could not parse initial-state as object")?;
+        Ok(Self {
+            accounts: parse_entries(object, "accounts")?
+                .iter()
+                .map(|account| {
+                    Ok(InitialAccount {
+                        address: parse_entry_str(account, "address")?
+                            .map(parse_address)
+                            .transpose()?
+                            .ok_or("could not find initial-state account address")?,
+                        balance: account.get("balance").map(parse_wei).transpose()?,
+                        code: parse_entry_str(account, "code")?
+                            .map(|code| {
+                                let code = hex::decode(code.strip_prefix("0x").unwrap_or(code))?;
+                                Ok::<_, Box<dyn error::Error>>(format!("0x{}", hex::encode(code)))
+                            })
+                            .transpose()?,
+                    })
+                })
+                .collect::<Result<_, Box<dyn error::Error>>>()?,
+            storage: parse_entries(object, "storage")?
+                .iter()
+                .map(|entry| {
+                    Ok(InitialStorage {
+                        address: parse_entry_str(entry, "address")?
+                            .map(parse_address)
+                            .transpose()?,
+                        slot: parse_word(
+                            parse_entry_str(entry, "slot")?
+                                .ok_or("could not find initial-state storage slot")?,
+                        )?,
+                        value: parse_word(
+                            parse_entry_str(entry, "value")?
+                                .ok_or("could not find initial-state storage value")?,
+                        )?,
+                    })
+                })
+                .collect::<Result<_, Box<dyn error::Error>>>()?,
+        })
+    }
+}
+
+fn parse_entries<'a>(
+    object: &'a serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Result<&'a [serde_json::Value], Box<dyn error::Error>> {
+    object.get(key).map_or(Ok(&[][..]), |x| {
+        Ok(x.as_array()
+            .ok_or_else(|| format!("could not parse initial-state {key} as array"))?)
+    })
+}
+
+fn parse_entry_str<'a>(
+    entry: &'a serde_json::Value,
+    key: &str,
+) -> Result<Option<&'a str>, Box<dyn error::Error>> {
+    Ok(entry
+        .get(key)
+        .map(|x| {
+            x.as_str()
+                .ok_or_else(|| format!("could not parse initial-state {key} as string"))
+        })
+        .transpose()?)
+}
+
+/// Parses a hex word of at most 32 bytes, padding it to 32 bytes.
+fn parse_word(word: &str) -> Result<String, Box<dyn error::Error>> {
+    let digits = word.strip_prefix("0x").unwrap_or(word);
+    if digits.len() > 64 {
+        return Err(format!("{word} is longer than 32 bytes").into());
+    }
+    let bytes = hex::decode(format!("{digits:0>64}"))?;
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+/// Parses an address like `0x1000000000000000000000000000000000000001`, normalizing it to
+/// lowercase hex with a `0x` prefix.
+fn parse_address(address: &str) -> Result<String, Box<dyn error::Error>> {
+    let bytes = hex::decode(address.strip_prefix("0x").unwrap_or(address))?;
+    if bytes.len() != 20 {
+        return Err(format!("{address} is not 20 bytes long").into());
+    }
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+/// Parses a wei amount given as either an integer or a string of decimal digits, so that amounts
+/// too large for JSON integers can be given.
+fn parse_wei(value: &serde_json::Value) -> Result<String, Box<dyn error::Error>> {
+    match value {
+        serde_json::Value::Number(number) => Ok(number
+            .as_u64()
+            .ok_or("could not parse value as a non-negative integer")?
+            .to_string()),
+        serde_json::Value::String(digits)
+            if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            Ok(digits.clone())
+        }
+        _ => Err("could not parse value as a non-negative integer".into()),
+    }
+}
+
+impl Benchmark {
+    /// Whether the benchmark contract is written in Vyper rather than Solidity.
+    pub fn is_vyper(&self) -> bool {
+        self.contract.extension().is_some_and(|ext| ext == "vy")
+    }
+}
+
+/// Rough cost of a single run of a benchmark, which benchmarks without an explicit number of
+/// runs use to pick one.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BenchmarkCost {
+    Cheap,
+    Moderate,
+    Expensive,
+}
+
+/// Number of runs for benchmarks of each cost.
+#[derive(Clone, Debug)]
+pub struct CostRunCounts {
+    pub cheap: u64,
+    pub moderate: u64,
+    pub expensive: u64,
+}
+
+impl Default for CostRunCounts {
+    fn default() -> Self {
+        Self {
+            cheap: 25,
+            moderate: 10,
+            expensive: 3,
+        }
+    }
+}
+
+impl BenchmarkCost {
+    /// Rough wall time of a single run of a benchmark of this cost, to estimate how long running
+    /// it takes before anything has been run.
+    pub fn run_time_prior(&self) -> Duration {
+        match self {
+            BenchmarkCost::Cheap => Duration::from_millis(10),
+            BenchmarkCost::Moderate => Duration::from_millis(100),
+            BenchmarkCost::Expensive => Duration::from_secs(1),
+        }
+    }
+}
+
+impl CostRunCounts {
+    pub fn num_runs(&self, cost: BenchmarkCost) -> u64 {
+        match cost {
+            BenchmarkCost::Cheap => self.cheap,
+            BenchmarkCost::Moderate => self.moderate,
+            BenchmarkCost::Expensive => self.expensive,
+        }
+    }
 }
 
 pub struct BenchmarkDefaults {
     pub solc_version: String,
     pub num_runs: u64,
     pub calldata: Vec<u8>,
+    pub cost_run_counts: CostRunCounts,
+}
+
+impl Default for BenchmarkDefaults {
+    fn default() -> Self {
+        Self {
+            solc_version: "stable".to_string(),
+            num_runs: 10,
+            calldata: Vec::new(),
+            cost_run_counts: CostRunCounts::default(),
+        }
+    }
+}
+
+/// Splits `params` at commas that are not inside a tuple.
+fn split_top_level_params(params: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(params[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(params[start..].trim());
+    split.retain(|param| !param.is_empty());
+    split
+}
+
+/// ABI-encodes a call of the function with Solidity `signature`, e.g. `run(uint256)`, with
+/// `args`. Arguments are given as JSON strings, numbers, or booleans.
+fn encode_function_call(
+    signature: &str,
+    args: &[serde_json::Value],
+) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    let (name, params) = signature
+        .trim()
+        .strip_suffix(')')
+        .and_then(|s| s.split_once('('))
+        .filter(|(name, _)| !name.is_empty())
+        .ok_or(format!("could not parse function signature {signature}"))?;
+    let param_types = split_top_level_params(params)
+        .into_iter()
+        .map(|param| {
+            Reader::read(param).map_err(|e| format!("could not parse parameter type {param}: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if args.len() != param_types.len() {
+        return Err(format!(
+            "function {signature} takes {} arguments, but {} were given",
+            param_types.len(),
+            args.len()
+        )
+        .into());
+    }
+
+    let tokens = param_types
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(i, (param_type, arg))| {
+            let value = match arg {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            LenientTokenizer::tokenize(param_type, &value).map_err(|e| {
+                format!("could not encode argument {i} ({value}) as {param_type}: {e}")
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let canonical_signature = format!(
+        "{name}({})",
+        param_types
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    let mut calldata = Keccak256::digest(canonical_signature.as_bytes())[..4].to_vec();
+    calldata.extend(ethabi::encode(&tokens));
+    Ok(calldata)
+}
+
+fn parse_optional_u64(
+    object: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Result<Option<u64>, Box<dyn error::Error>> {
+    object
+        .get(key)
+        .map(|x| {
+            x.as_u64()
+                .ok_or_else(|| format!("could not parse {key} as u64").into())
+        })
+        .transpose()
 }
 
 impl MetadataParser for Benchmark {
     type Defaults = BenchmarkDefaults;
 
+    const INHERITED_FILE_NAME: Option<&'static str> = Some(BENCHMARK_DEFAULTS_FILE_NAME);
+
     fn parse_inner(
         base_path: &Path,
         json: &serde_json::Value,
@@ -81,6 +611,10 @@ impl MetadataParser for Benchmark {
     ) -> Result<Self, Box<dyn error::Error>> {
         log::trace!("parsing benchmark metadata...");
         let object = json.as_object().expect("could not parse json as object");
+        let cost = object
+            .get("cost")
+            .map(|x| serde_json::from_value::<BenchmarkCost>(x.clone()))
+            .transpose()?;
         let benchmark = Self {
             name: object
                 .get("name")
@@ -95,11 +629,13 @@ impl MetadataParser for Benchmark {
                     |x| Ok(x.as_str().ok_or("could not parse solc-version as string")?),
                 )?
                 .to_string(),
-            num_runs: object
-                .get("num-runs")
-                .map_or(Ok::<u64, Box<dyn error::Error>>(defaults.num_runs), |x| {
-                    Ok(x.as_u64().ok_or("could not parse num-runs as u64")?)
-                })?,
+            num_runs: match object.get("num-runs") {
+                Some(x) => x.as_u64().ok_or("could not parse num-runs as u64")?,
+                None => cost.map_or(defaults.num_runs, |cost| {
+                    defaults.cost_run_counts.num_runs(cost)
+                }),
+            },
+            cost,
             contract: base_path
                 .join(PathBuf::from(
                     object
@@ -119,15 +655,88 @@ impl MetadataParser for Benchmark {
                     },
                 )?))
                 .canonicalize()?,
-            calldata: object.get("calldata").map_or(
-                Ok::<Vec<u8>, Box<dyn error::Error>>(defaults.calldata.clone()),
-                |x| {
-                    Ok(hex::decode(
-                        x.as_str().ok_or("could not parse calldata as bytes")?,
+            calldata: match (object.get("calldata"), object.get("function")) {
+                (Some(_), Some(_)) => {
+                    return Err("only one of calldata and function can be given".into())
+                }
+                (None, Some(function)) => encode_function_call(
+                    function
+                        .as_str()
+                        .ok_or("could not parse function as string")?,
+                    object
+                        .get("args")
+                        .map(|x| x.as_array().ok_or("could not parse args as array"))
+                        .transpose()?
+                        .map_or(&[][..], Vec::as_slice),
+                )?,
+                (calldata, None) => calldata.map_or(
+                    Ok::<Vec<u8>, Box<dyn error::Error>>(defaults.calldata.clone()),
+                    |x| {
+                        Ok(hex::decode(
+                            x.as_str().ok_or("could not parse calldata as bytes")?,
+                        )?)
+                    },
+                )?,
+            },
+            evm_config: EvmConfig {
+                gas_limit: parse_optional_u64(object, "gas-limit")?,
+                chain_id: parse_optional_u64(object, "chain-id")?,
+                base_fee: parse_optional_u64(object, "base-fee")?,
+                caller: object
+                    .get("caller")
+                    .map(|x| {
+                        parse_address(x.as_str().ok_or("could not parse caller as string")?)
+                            .map_err(|e| format!("could not parse caller as an address: {e}"))
+                    })
+                    .transpose()?,
+                value: object.get("value").map(parse_wei).transpose()?,
+            },
+            inner_iterations: parse_optional_u64(object, "inner-iterations")?
+                .unwrap_or(1)
+                .max(1),
+            weight: match object.get("weight") {
+                Some(x) => {
+                    let weight = x.as_f64().ok_or("could not parse weight as f64")?;
+                    if !weight.is_finite() || weight < 0.0 {
+                        return Err("weight must be a non-negative number".into());
+                    }
+                    BenchmarkWeight(weight)
+                }
+                None => BenchmarkWeight::default(),
+            },
+            mode: object
+                .get("mode")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?
+                .unwrap_or_default(),
+            duration_ms: parse_optional_u64(object, "duration-ms")?,
+            initial_state: object
+                .get("initial-state")
+                .map(InitialState::parse)
+                .transpose()?,
+            compiler_version: None,
+            calldata_sweep: object
+                .get("calldata-sweep")
+                .map(CalldataSweep::parse)
+                .transpose()?,
+            calldata_sweep_size: None,
+            setup_calldata: object
+                .get("setup-calldata")
+                .map(|x| {
+                    Ok::<_, Box<dyn error::Error>>(hex::decode(
+                        x.as_str()
+                            .ok_or("could not parse setup calldata as string")?
+                            .trim_start_matches("0x"),
                     )?)
-                },
-            )?,
+                })
+                .transpose()?,
         };
+        if benchmark.duration_ms.is_some() && benchmark.mode != BenchmarkMode::Throughput {
+            return Err("duration-ms only applies to benchmarks with a mode of throughput".into());
+        }
+        if benchmark.duration_ms == Some(0) {
+            return Err("duration-ms must be positive".into());
+        }
         log::debug!("parsed benchmark metadata: {}", &benchmark.name);
         log::trace!("benchmark metadata: {:?}", benchmark);
         Ok(benchmark)
@@ -137,19 +746,131 @@ impl MetadataParser for Benchmark {
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct Runner {
     pub name: String,
-    pub entry: PathBuf,
+    /// Executable that implements the runner interface. Exactly one of this and `image` is set.
+    #[serde(default)]
+    pub entry: Option<PathBuf>,
+    /// Docker image, as a registry reference, whose entrypoint implements the runner interface,
+    /// for runners that are published as images rather than built locally.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Remote repository the runner's entry lives in, if it is not alongside its metadata.
+    #[serde(default)]
+    pub git: Option<GitSource>,
+    /// Environment variables the entry is run with, e.g. to pick the version of the EVM it
+    /// builds.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Template of the arguments to run the entry with, instead of the standard runner
+    /// interface, in which `{contract_code_path}`, `{calldata}`, and `{num_runs}` are substituted.
+    #[serde(default)]
+    pub args: Option<Vec<String>>,
+    /// What the host needs for the runner to work on it.
+    #[serde(default)]
+    pub requirements: RunnerRequirements,
+    /// Names, or globs like `erc20*`, of benchmarks the runner cannot run, e.g. because it lacks
+    /// an opcode or precompile they use.
+    #[serde(default)]
+    pub unsupported_benchmarks: Vec<String>,
+    /// Optional options of the runner interface that the runner accepts. Benchmarks that need
+    /// any other are not run on it.
+    #[serde(default)]
+    pub options: BTreeSet<RunnerOption>,
+}
+
+/// An optional option of the runner interface, named as on the command line.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunnerOption {
+    GasLimit,
+    ChainId,
+    BaseFee,
+    Caller,
+    Value,
+    InnerIterations,
+    Mode,
+    DurationMs,
+    StatePath,
+    SetupCalldata,
+    CalldataPath,
+    PrintReturnData,
+    PrintReturnDataHashes,
+    MaxPassMicros,
+    Profile,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunnerRequirements {
+    pub min_memory_bytes: Option<u64>,
+    /// CPU feature flags, as named in `/proc/cpuinfo` (e.g. `avx2`).
+    #[serde(default)]
+    pub cpu_flags: Vec<String>,
+}
+
+impl Runner {
+    /// Whether the runner can run `benchmark`: it is not listed as unsupported, and the runner
+    /// accepts every option the benchmark needs.
+    pub fn supports(&self, benchmark: &Benchmark) -> bool {
+        !self
+            .unsupported_benchmarks
+            .iter()
+            .any(|pattern| name_matches(pattern, &benchmark.name))
+            && benchmark
+                .required_options()
+                .iter()
+                .all(|&option| self.accepts(option))
+    }
+
+    /// Whether the runner accepts the optional runner interface option `option`. Runners with an
+    /// argument template accept none, since the template has nowhere to put them.
+    pub fn accepts(&self, option: RunnerOption) -> bool {
+        self.args.is_none() && self.options.contains(&option)
+    }
+
+    /// Fills in the runner's argument template, if it has one.
+    pub fn templated_args(
+        &self,
+        contract_code_path: &str,
+        calldata: &str,
+        num_runs: u64,
+    ) -> Option<Vec<String>> {
+        Some(
+            self.args
+                .as_ref()?
+                .iter()
+                .map(|arg| {
+                    arg.replace("{contract_code_path}", contract_code_path)
+                        .replace("{calldata}", calldata)
+                        .replace("{num_runs}", &num_runs.to_string())
+                })
+                .collect(),
+        )
+    }
+}
+
+pub struct RunnerDefaults {
+    /// Where runners with a git source are checked out to.
+    pub git_cache_path: PathBuf,
 }
 
 impl MetadataParser for Runner {
-    type Defaults = ();
+    type Defaults = RunnerDefaults;
 
     fn parse_inner(
         base_path: &Path,
         json: &serde_json::Value,
-        _: &Self::Defaults,
+        defaults: &Self::Defaults,
     ) -> Result<Self, Box<dyn error::Error>> {
         log::trace!("parsing runner metadata...");
         let object = json.as_object().expect("could not parse json as object");
+        let git = object
+            .get("git")
+            .map(|x| serde_json::from_value::<GitSource>(x.clone()))
+            .transpose()?;
+        let base_path = match &git {
+            Some(git) => checkout(git, &defaults.git_cache_path)?,
+            None => base_path.to_path_buf(),
+        };
         let runner = Self {
             name: object
                 .get("name")
@@ -157,22 +878,361 @@ impl MetadataParser for Runner {
                 .as_str()
                 .ok_or("could not parse name as string")?
                 .to_string(),
-            entry: base_path
-                .join(PathBuf::from(
-                    object
-                        .get("entry")
-                        .ok_or("could not find entry")?
-                        .as_str()
-                        .ok_or("could not parse entry as string")?,
-                ))
-                .canonicalize()?,
+            entry: object
+                .get("entry")
+                .map(|x| {
+                    Ok::<_, Box<dyn error::Error>>(
+                        base_path
+                            .join(x.as_str().ok_or("could not parse entry as string")?)
+                            .canonicalize()?,
+                    )
+                })
+                .transpose()?,
+            image: object
+                .get("image")
+                .map(|x| {
+                    x.as_str()
+                        .map(str::to_string)
+                        .ok_or("could not parse image as string")
+                })
+                .transpose()?,
+            git,
+            env: object
+                .get("env")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?
+                .unwrap_or_default(),
+            args: object
+                .get("args")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?,
+            requirements: object
+                .get("requirements")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?
+                .unwrap_or_default(),
+            unsupported_benchmarks: object
+                .get("unsupported-benchmarks")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?
+                .unwrap_or_default(),
+            options: object
+                .get("options")
+                .map(|x| serde_json::from_value(x.clone()))
+                .transpose()?
+                .unwrap_or_default(),
         };
+        match (&runner.entry, &runner.image) {
+            (Some(_), Some(_)) => return Err("only one of entry and image can be set".into()),
+            (None, None) => return Err("could not find entry or image".into()),
+            (None, Some(_)) if runner.git.is_some() => {
+                return Err("git only applies to runners with an entry".into())
+            }
+            _ => {}
+        }
+        if runner.args.is_some() && !runner.options.is_empty() {
+            return Err("options only apply to runners without args".into());
+        }
         log::debug!("parsed runner metadata: {}", &runner.name);
         log::trace!("runner metadata: {:?}", runner);
         Ok(runner)
     }
 }
 
+const YAML_EXTENSIONS: [&str; 2] = ["yaml", "yml"];
+
+/// Reads a metadata file as JSON, whether it is authored as JSON or as YAML.
+fn read_metadata_json(metadata_path: &Path) -> Result<serde_json::Value, Box<dyn error::Error>> {
+    let metadata = fs::read_to_string(metadata_path)?;
+    metadata_json_from_str(&metadata, is_yaml_path(metadata_path))
+}
+
+fn metadata_json_from_str(
+    metadata: &str,
+    yaml: bool,
+) -> Result<serde_json::Value, Box<dyn error::Error>> {
+    // Both deserializers report the line and column of syntax errors.
+    Ok(if yaml {
+        serde_yaml::from_str(metadata)?
+    } else {
+        serde_json::from_str(metadata)?
+    })
+}
+
+/// Checks `json` against `schema`, describing every value that fails it by its JSON pointer and
+/// the line `locate` finds it on, if any.
+fn validate_against_schema(
+    schema: &serde_json::Value,
+    json: &serde_json::Value,
+    locate: impl Fn(&[String]) -> Option<usize>,
+) -> Result<(), Box<dyn error::Error>> {
+    let compiled_schema = jsonschema::JSONSchema::compile(schema)
+        .map_err(|e| format!("could not compile schema: {e}"))?;
+    if let Err(errors) = compiled_schema.validate(json) {
+        let errors = errors
+            .map(|e| {
+                let field = e.instance_path.to_string();
+                if field.is_empty() {
+                    return e.to_string();
+                }
+                match locate(&e.instance_path.clone().into_vec()) {
+                    Some(line) => format!("at {field} (line {line}): {e}"),
+                    None => format!("at {field}: {e}"),
+                }
+            })
+            .collect::<Vec<_>>();
+        return Err(format!("does not abide by the schema, {}", errors.join("; ")).into());
+    }
+    Ok(())
+}
+
+/// What [`PointerSeed`] expects of the value it looks for, which it rejects so that the
+/// deserializer raises an error tagged with where that value starts.
+const POINTER_FOUND: &str = "found pointer";
+
+/// Walks a metadata file down the path of a JSON pointer, without building any values, and fails
+/// at the value the pointer refers to.
+struct PointerSeed<'a>(&'a [String]);
+
+impl<'de> de::DeserializeSeed<'de> for PointerSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for PointerSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            formatter.write_str(POINTER_FOUND)
+        } else {
+            formatter.write_str("an object or array")
+        }
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let Some((next, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(POINTER_FOUND));
+        };
+        while let Some(key) = map.next_key::<String>()? {
+            if key == *next {
+                map.next_value_seed(PointerSeed(rest))?;
+            } else {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let Some((next, rest)) = self.0.split_first() else {
+            return Err(de::Error::custom(POINTER_FOUND));
+        };
+        let mut index = 0;
+        loop {
+            let element = if index.to_string() == *next {
+                seq.next_element_seed(PointerSeed(rest))?
+            } else {
+                seq.next_element::<de::IgnoredAny>()?.map(|_| ())
+            };
+            if element.is_none() {
+                return Ok(());
+            }
+            index += 1;
+        }
+    }
+}
+
+/// Line of `metadata`, the contents of a metadata file, that the value at the JSON pointer
+/// `pointer` is on, or `None` if the file does not give that value itself.
+fn line_of_pointer(metadata: &str, yaml: bool, pointer: &[String]) -> Option<usize> {
+    let seed = PointerSeed(pointer);
+    if yaml {
+        let e =
+            de::DeserializeSeed::deserialize(seed, serde_yaml::Deserializer::from_str(metadata))
+                .err()?;
+        e.to_string()
+            .contains(POINTER_FOUND)
+            .then_some(e.location()?.line())
+    } else {
+        let e = de::DeserializeSeed::deserialize(
+            seed,
+            &mut serde_json::Deserializer::from_str(metadata),
+        )
+        .err()?;
+        (e.to_string().contains(POINTER_FOUND) && e.line() > 0).then_some(e.line())
+    }
+}
+
+/// Keys that the metadata file at `metadata_path` inherits from `file_name` files in its
+/// directory and the directories above it, up to and including `search_path`. Nearer files take
+/// precedence over farther ones.
+fn read_inherited_keys(
+    search_path: &Path,
+    metadata_path: &Path,
+    file_name: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn error::Error>> {
+    let mut inherited = serde_json::Map::new();
+    let directories = metadata_path
+        .ancestors()
+        .skip(1)
+        .take_while(|directory| directory.starts_with(search_path));
+    for directory in directories {
+        for file_name in metadata_file_names(file_name) {
+            let path = directory.join(file_name);
+            if !path.is_file() {
+                continue;
+            }
+            let json = read_metadata_json(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            let object = json
+                .as_object()
+                .ok_or_else(|| format!("{}: could not parse as object", path.display()))?;
+            for (key, value) in object {
+                // Schemas are referenced relative to each file, so are not inherited.
+                if key != "$schema" && !inherited.contains_key(key) {
+                    inherited.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    Ok(inherited)
+}
+
+fn is_yaml_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| YAML_EXTENSIONS.iter().any(|yaml| ext == *yaml))
+}
+
+/// Metadata may be authored as JSON (`file_name` itself) or as YAML, in which case the
+/// extension of `file_name` is swapped for `.yaml` or `.yml`.
+fn metadata_file_names(file_name: &str) -> Vec<String> {
+    let mut file_names = vec![file_name.to_string()];
+    for ext in YAML_EXTENSIONS {
+        let yaml_file_name = Path::new(file_name)
+            .with_extension(ext)
+            .to_string_lossy()
+            .to_string();
+        if !file_names.contains(&yaml_file_name) {
+            file_names.push(yaml_file_name);
+        }
+    }
+    file_names
+}
+
+/// Which benchmarks or runners to use, by name. Names may be globs like `erc20*`.
+#[derive(Clone, Debug, Default)]
+pub struct NameFilter {
+    /// Names to use, or `None` to use everything not excluded.
+    pub include: Option<Vec<String>>,
+    pub exclude: Vec<String>,
+}
+
+impl NameFilter {
+    /// Builds a filter from names given directly, merged with those in a set file. Set files are
+    /// either a JSON list of names or newline-delimited names, where names prefixed with `!` are
+    /// excluded instead. Blank lines and lines starting with `#` are skipped.
+    pub fn new(
+        names: Option<Vec<String>>,
+        set_path: Option<&Path>,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let mut filter = NameFilter {
+            include: names,
+            exclude: Vec::new(),
+        };
+        let Some(set_path) = set_path else {
+            return Ok(filter);
+        };
+
+        let contents = fs::read_to_string(set_path)?;
+        let entries = match serde_json::from_str::<Vec<String>>(&contents) {
+            Ok(entries) => entries,
+            Err(_) => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+        };
+        for entry in entries {
+            match entry.strip_prefix('!') {
+                Some(name) => filter.exclude.push(name.to_string()),
+                None => filter.include.get_or_insert_with(Vec::new).push(entry),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.iter().any(|n| name_matches(n, name)))
+            && !self.exclude.iter().any(|n| name_matches(n, name))
+    }
+}
+
+/// Whether `name` matches the glob `pattern`, or equals it if it is not a valid glob.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    Pattern::new(pattern).map_or(pattern == name, |pattern| pattern.matches(name))
+}
+
+/// Name of the file, at the root of a search path, listing metadata to leave out of discovery.
+pub const IGNORE_FILE_NAME: &str = ".evmbenchignore";
+
+/// Reads the gitignore-style globs in the ignore file at the root of `search_path`, if any.
+/// Blank lines and lines starting with `#` are skipped, and negation is not supported.
+fn read_ignore_patterns(search_path: &Path) -> Result<Vec<Pattern>, Box<dyn error::Error>> {
+    let ignore_path = search_path.join(IGNORE_FILE_NAME);
+    if !ignore_path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let mut patterns = Vec::new();
+    for line in fs::read_to_string(&ignore_path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('!') {
+            log::warn!(
+                "negated pattern {line} in {} is not supported",
+                ignore_path.display()
+            );
+            continue;
+        }
+        patterns.push(Pattern::new(line.trim_end_matches('/'))?);
+    }
+    Ok(patterns)
+}
+
+/// Whether `relative_path` is matched by any of `patterns`. Like in gitignore, a pattern without
+/// a slash matches any file or directory name along the path, and a pattern with one matches
+/// the path, or a directory containing it, from the search path root.
+fn is_ignored(patterns: &[Pattern], relative_path: &Path) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let components = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    patterns.iter().any(|pattern| {
+        if pattern.as_str().contains('/') {
+            let pattern = Pattern::new(pattern.as_str().trim_start_matches('/'))
+                .unwrap_or_else(|_| pattern.clone());
+            (1..=components.len())
+                .any(|i| pattern.matches_with(&components[..i].join("/"), options))
+        } else {
+            components
+                .iter()
+                .any(|component| pattern.matches_with(component, options))
+        }
+    })
+}
+
 fn find_metadata<T: MetadataParser>(
     file_name: &str,
     schema_path: &Path,
@@ -186,22 +1246,42 @@ fn find_metadata<T: MetadataParser>(
         return Err(format!("{} is not a directory", search_path.display()).into());
     }
 
-    Ok(
-        glob(&search_path.join("**").join(file_name).to_string_lossy())?
-            .flat_map(|entry| match entry {
-                Ok(path) => {
-                    log::debug!(
-                        "found {}",
-                        path.strip_prefix(&search_path).unwrap_or(&path).display()
-                    );
-                    Some(path)
-                }
-                Err(e) => {
-                    log::warn!("error globing file: {:?}", e);
-                    None
+    let ignore_patterns = read_ignore_patterns(&search_path)?;
+
+    let mut paths = Vec::new();
+    for file_name in metadata_file_names(file_name) {
+        paths.extend(glob(
+            &search_path.join("**").join(file_name).to_string_lossy(),
+        )?);
+    }
+
+    Ok(paths
+        .into_iter()
+        .flat_map(|entry| match entry {
+            Ok(path) => {
+                let relative_path = path.strip_prefix(&search_path).unwrap_or(&path);
+                if is_ignored(&ignore_patterns, relative_path) {
+                    log::debug!("ignoring {}", relative_path.display());
+                    return None;
                 }
-            })
-            .flat_map(|path| match T::parse_from_file(&schema, &path, &defaults) {
+                log::debug!("found {}", relative_path.display());
+                Some(path)
+            }
+            Err(e) => {
+                log::warn!("error globing file: {:?}", e);
+                None
+            }
+        })
+        .flat_map(|path| {
+            let parsed = T::INHERITED_FILE_NAME
+                .map_or_else(
+                    || Ok(serde_json::Map::new()),
+                    |file_name| read_inherited_keys(&search_path, &path, file_name),
+                )
+                .and_then(|inherited| {
+                    T::parse_from_file_inheriting(&schema, &path, &inherited, &defaults)
+                });
+            match parsed {
                 Ok(res) => {
                     log::debug!(
                         "parsed {}",
@@ -210,12 +1290,25 @@ fn find_metadata<T: MetadataParser>(
                     Some(res)
                 }
                 Err(e) => {
-                    log::warn!("error parsing file: {:?}", e);
+                    log::warn!("error parsing file: {e}");
                     None
                 }
-            })
-            .collect(),
-    )
+            }
+        })
+        .collect())
+}
+
+/// Parses a single benchmark metadata file, without searching for any others.
+pub fn parse_benchmark(
+    metadata_path: &Path,
+    schema_path: &Path,
+    benchmark_defaults: BenchmarkDefaults,
+) -> Result<Benchmark, EvmBenchError> {
+    (|| {
+        let schema = Benchmark::parse_schema_from_file(schema_path)?;
+        Benchmark::parse_from_file(&schema, &metadata_path.canonicalize()?, &benchmark_defaults)
+    })()
+    .map_err(EvmBenchError::MetadataParse)
 }
 
 pub fn find_benchmarks(
@@ -223,15 +1316,18 @@ pub fn find_benchmarks(
     schema_path: &Path,
     search_path: &Path,
     benchmark_defaults: BenchmarkDefaults,
-) -> Result<Vec<Benchmark>, Box<dyn error::Error>> {
+) -> Result<Vec<Benchmark>, EvmBenchError> {
     let benchmarks =
-        find_metadata::<Benchmark>(file_name, schema_path, search_path, benchmark_defaults)?;
+        find_metadata::<Benchmark>(file_name, schema_path, search_path, benchmark_defaults)
+            .map_err(EvmBenchError::MetadataParse)?;
     let benchmark_names = benchmarks
         .iter()
         .map(|b| b.name.clone())
         .collect::<HashSet<_>>();
     if benchmark_names.len() != benchmarks.len() {
-        Err("found duplicate benchmark names".into())
+        Err(EvmBenchError::MetadataParse(
+            "found duplicate benchmark names".into(),
+        ))
     } else {
         log::info!(
             "found {} benchmarks: {}",
@@ -246,19 +1342,72 @@ pub fn find_benchmarks(
     }
 }
 
+/// Name of the benchmark metadata files searched for by default.
+pub const BENCHMARK_METADATA_NAME: &str = "benchmark.evm-bench.json";
+/// Name of the files whose keys every benchmark metadata file in the same directory, or any
+/// directory below it, inherits when it does not give those keys itself.
+pub const BENCHMARK_DEFAULTS_FILE_NAME: &str = "benchmark-defaults.evm-bench.json";
+/// Name of the runner metadata files searched for by default.
+pub const RUNNER_METADATA_NAME: &str = "runner.evm-bench.json";
+/// Name of the schema, at the root of each search path, that metadata is checked against.
+const SCHEMA_FILE_NAME: &str = "schema.json";
+
+/// Every benchmark and runner in a suite, as found by [`discover`].
+#[derive(Clone, Debug)]
+pub struct DiscoveredSuite {
+    /// Benchmarks, in order of their names, with the paths to their contracts.
+    pub benchmarks: Vec<Benchmark>,
+    /// Runners, in order of their names, with the paths to their entries or their images.
+    pub runners: Vec<Runner>,
+}
+
+/// Finds every benchmark under `benchmarks_path` and every runner under `runners_path`, with
+/// default metadata file names and the schemas at the root of each path, as the CLI does by
+/// default. Nothing is compiled and Docker is not involved, so this is cheap enough to list or
+/// validate a suite before running it. Runners with a git source are still checked out, to a
+/// cache in the system's temporary directory.
+pub fn discover(
+    benchmarks_path: &Path,
+    runners_path: &Path,
+) -> Result<DiscoveredSuite, EvmBenchError> {
+    let mut benchmarks = find_benchmarks(
+        BENCHMARK_METADATA_NAME,
+        &benchmarks_path.join(SCHEMA_FILE_NAME),
+        benchmarks_path,
+        BenchmarkDefaults::default(),
+    )?;
+    benchmarks.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut runners = find_runners(
+        RUNNER_METADATA_NAME,
+        &runners_path.join(SCHEMA_FILE_NAME),
+        runners_path,
+        RunnerDefaults {
+            git_cache_path: env::temp_dir().join("evm-bench-git"),
+        },
+    )?;
+    runners.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(DiscoveredSuite {
+        benchmarks,
+        runners,
+    })
+}
+
 pub fn find_runners(
     file_name: &str,
     schema_path: &Path,
     search_path: &Path,
-    runner_defaults: (),
-) -> Result<Vec<Runner>, Box<dyn error::Error>> {
-    let runners = find_metadata::<Runner>(file_name, schema_path, search_path, runner_defaults)?;
+    runner_defaults: RunnerDefaults,
+) -> Result<Vec<Runner>, EvmBenchError> {
+    let runners = find_metadata::<Runner>(file_name, schema_path, search_path, runner_defaults)
+        .map_err(EvmBenchError::MetadataParse)?;
     let runner_names = runners
         .iter()
         .map(|b| b.name.clone())
         .collect::<HashSet<_>>();
     if runner_names.len() != runners.len() {
-        Err("found duplicate runners names".into())
+        Err(EvmBenchError::MetadataParse(
+            "found duplicate runners names".into(),
+        ))
     } else {
         log::info!(
             "found {} runners: {}",
@@ -268,3 +1417,54 @@ pub fn find_runners(
         Ok(runners)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_file_names_are_unique() {
+        assert_eq!(
+            metadata_file_names("benchmark.evm-bench.json"),
+            [
+                "benchmark.evm-bench.json",
+                "benchmark.evm-bench.yaml",
+                "benchmark.evm-bench.yml",
+            ]
+        );
+        assert_eq!(metadata_file_names("x.yml"), ["x.yml", "x.yaml"]);
+        assert_eq!(metadata_file_names("x.yaml"), ["x.yaml", "x.yml"]);
+    }
+
+    #[test]
+    fn schema_errors_point_at_lines() {
+        let pointer = |pointer: &[&str]| pointer.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let json = "{\n  \"name\": \"x\",\n  \"calldata\": [\n    1,\n    \"two\"\n  ]\n}\n";
+        assert_eq!(line_of_pointer(json, false, &pointer(&["name"])), Some(2));
+        assert_eq!(
+            line_of_pointer(json, false, &pointer(&["calldata", "1"])),
+            Some(5)
+        );
+        assert_eq!(line_of_pointer(json, false, &pointer(&["missing"])), None);
+
+        let yaml = "name: x\ncalldata:\n  - 1\n  - two\n";
+        assert_eq!(line_of_pointer(yaml, true, &pointer(&["name"])), Some(1));
+        assert_eq!(
+            line_of_pointer(yaml, true, &pointer(&["calldata", "1"])),
+            Some(4)
+        );
+        assert_eq!(line_of_pointer(yaml, true, &pointer(&["missing"])), None);
+
+        let schema = serde_json::json!({
+            "properties": { "calldata": { "items": { "type": "integer" } } },
+        });
+        let error = validate_against_schema(&schema, &serde_json::from_str(json).unwrap(), |p| {
+            line_of_pointer(json, false, p)
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "does not abide by the schema, at /calldata/1 (line 5): \"two\" is not of type \"integer\""
+        );
+    }
+}
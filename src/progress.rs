@@ -0,0 +1,23 @@
+//! Progress of the build and run phases, for embedders that show their own progress display.
+
+/// What happened to an item of a phase.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProgressState {
+    Started,
+    Succeeded,
+    Failed,
+    /// The item was not attempted, e.g. to stay within the time budget.
+    Skipped,
+}
+
+/// An update on an item of a phase, e.g. a benchmark being built or a benchmark being run on a
+/// runner.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress<T> {
+    pub item: T,
+    pub state: ProgressState,
+    /// Number of items of the phase that are done, including this one if it just finished.
+    pub done: usize,
+    /// Number of items in the phase.
+    pub total: usize,
+}
@@ -1,19 +1,31 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     error,
     fs::{self, create_dir_all},
-    io::Write,
+    io::{self, Write},
     path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
     time::Duration,
 };
 
-use chrono;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, Local, Offset, Utc,
+};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use tabled::{builder::Builder, Style};
+use sha3::{Digest, Keccak256};
+use tabled::{builder::Builder, Style, Table};
 
 use crate::{
-    metadata::{Benchmark, Runner},
-    run::{Results, RunResult},
+    build::{BuildFailure, BuiltBenchmark, BuiltBenchmarks},
+    host::SkippedRunner,
+    metadata::{Benchmark, Runner, RunnerOption},
+    run::{
+        Results, RunConfig, RunFailure, RunOutput, RunResult, RunSummary, RunTiming, StabilityCheck,
+    },
+    stats,
 };
 
 #[derive(Deserialize, Serialize)]
@@ -21,20 +33,792 @@ struct ResultsFormatted {
     benchmarks: HashMap<String, Benchmark>,
     runners: HashMap<String, Runner>,
     runs: HashMap<String, HashMap<String, RunResult>>,
+    #[serde(default)]
+    failed_builds: Vec<BuildFailure>,
+    #[serde(default)]
+    run_config: RunConfig,
+    /// `(benchmark, runner)` pairs in the order they were run.
+    #[serde(default)]
+    run_order: Vec<(String, String)>,
+    /// `(benchmark, runner)` pairs skipped for exceeding the time budget.
+    #[serde(default)]
+    skipped_runs: Vec<(String, String)>,
+    /// `(benchmark, runner)` pairs not run because the runner does not support the benchmark.
+    #[serde(default)]
+    unsupported_runs: Vec<(String, String)>,
+    #[serde(default)]
+    failed_runs: Vec<RunFailure>,
+    /// Runners not used because the host does not meet their requirements.
+    #[serde(default)]
+    skipped_runners: Vec<SkippedRunner>,
+    #[serde(default)]
+    run_summary: Option<RunSummary>,
+    /// Whether the run was interrupted, leaving these results incomplete.
+    #[serde(default)]
+    incomplete: bool,
+    #[serde(default)]
+    timings: Option<Timings>,
+    /// Re-runs of each benchmark on its fastest runner, to check the environment was stable.
+    #[serde(default)]
+    stability_checks: Vec<StabilityCheck>,
+}
+
+/// Where the wall time of building and running went, to help speed up the harness itself.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Timings {
+    /// How long building each benchmark took.
+    pub builds: HashMap<String, Duration>,
+    pub runs: Vec<RunTiming>,
+}
+
+impl Timings {
+    /// Collects how long building `built_benchmarks` and each run in `run_output` took.
+    pub fn new(built_benchmarks: &[BuiltBenchmark], run_output: &RunOutput) -> Self {
+        Self {
+            builds: built_benchmarks
+                .iter()
+                .map(|b| (b.benchmark.name.clone(), b.result.build_time))
+                .collect(),
+            runs: run_output.timings.clone(),
+        }
+    }
+
+    pub fn total_build_time(&self) -> Duration {
+        self.builds.values().sum()
+    }
+
+    pub fn total_run_time(&self) -> Duration {
+        self.runs.iter().map(|run| run.wall_time).sum()
+    }
+
+    /// Time spent running outside of what runners measured, e.g. on process startup.
+    pub fn total_run_overhead(&self) -> Duration {
+        self.runs.iter().map(RunTiming::overhead).sum()
+    }
+}
+
+const RESULTS_FILE_SUFFIX: &str = ".evm-bench.results.json";
+const BASELINE_RESULTS_FILE_NAME: &str = "baseline.evm-bench.results.json";
+
+/// How results files are compressed, by running an external compressor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    const ALL: [Compression; 2] = [Compression::Gzip, Compression::Zstd];
+
+    /// Name of the executable that compresses and decompresses in this format.
+    pub fn executable(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Extension appended to the names of files compressed in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// Format that the file at `path` is compressed in, going by its extension.
+    fn of(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|compression| file_name.ends_with(compression.extension()))
+    }
+}
+
+/// Compresses the results file at `results_file_path`, replacing it with a compressed file named
+/// with the extension of `compression` appended, whose path is returned. Compressed results can
+/// be read like any others, as long as the compressor is available.
+pub fn compress_results(
+    results_file_path: &Path,
+    compression: Compression,
+) -> Result<PathBuf, Box<dyn error::Error>> {
+    let mut compressed_path = results_file_path.as_os_str().to_owned();
+    compressed_path.push(compression.extension());
+    let compressed_path = PathBuf::from(compressed_path);
+    // Without `--force`, both compressors refuse to overwrite existing files.
+    let mut command = Command::new(compression.executable());
+    if compression == Compression::Zstd {
+        command.args(["--rm", "-q"]);
+    }
+    let out = command.arg(results_file_path).output()?;
+    if !out.status.success() {
+        return Err(format!(
+            "could not compress {}: {}",
+            results_file_path.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+        .into());
+    }
+    log::info!("compressed results to {}", compressed_path.display());
+    Ok(compressed_path)
+}
+
+/// Contents of the results file at `results_file_path`, decompressed if need be.
+fn read_results_file(results_file_path: &Path) -> Result<String, Box<dyn error::Error>> {
+    let Some(compression) = Compression::of(results_file_path) else {
+        return Ok(fs::read_to_string(results_file_path)?);
+    };
+    let out = Command::new(compression.executable())
+        .args(["-d", "-c"])
+        .arg(results_file_path)
+        .output()
+        .map_err(|e| {
+            format!(
+                "could not run {} to decompress {}: {e}",
+                compression.executable(),
+                results_file_path.display()
+            )
+        })?;
+    if !out.status.success() {
+        return Err(format!(
+            "could not decompress {}: {}",
+            results_file_path.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+/// Name of the results file recorded at `timestamp` when no explicit name is given.
+pub fn results_file_name(timestamp: &DateTime<Utc>) -> String {
+    format!("{}{RESULTS_FILE_SUFFIX}", timestamp.to_rfc3339())
+}
+
+/// Timezone that timestamps in results file names are written in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OutputTimezone {
+    #[default]
+    Utc,
+    Local,
+    /// A fixed offset from UTC, like `+05:30`.
+    Fixed(FixedOffset),
+}
+
+impl FromStr for OutputTimezone {
+    type Err = String;
+
+    fn from_str(timezone: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{timezone} is not utc, local, or an offset like +05:30");
+        match timezone.to_lowercase().as_str() {
+            "utc" => return Ok(Self::Utc),
+            "local" => return Ok(Self::Local),
+            _ => {}
+        }
+        let (sign, offset) = match timezone.split_at_checked(1) {
+            Some(("+", offset)) => (1, offset),
+            Some(("-", offset)) => (-1, offset),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = offset.split_once(':').ok_or_else(invalid)?;
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Self::Fixed)
+            .ok_or_else(invalid)
+    }
+}
+
+/// How results files are named when no explicit name is given: by the time they are recorded
+/// at, as RFC 3339 in UTC by default.
+#[derive(Clone, Debug, Default)]
+pub struct ResultsFileNaming {
+    /// `strftime`-style format of the timestamp.
+    pub time_format: Option<String>,
+    pub timezone: OutputTimezone,
+}
+
+impl ResultsFileNaming {
+    /// Name of the results file recorded at `timestamp`.
+    pub fn file_name(&self, timestamp: &DateTime<Utc>) -> Result<String, Box<dyn error::Error>> {
+        let offset = match self.timezone {
+            OutputTimezone::Utc => FixedOffset::east_opt(0).unwrap(),
+            OutputTimezone::Local => timestamp.with_timezone(&Local).offset().fix(),
+            OutputTimezone::Fixed(offset) => offset,
+        };
+        let timestamp = timestamp.with_timezone(&offset);
+        let name = match &self.time_format {
+            Some(time_format) => {
+                if StrftimeItems::new(time_format).any(|item| item == Item::Error) {
+                    return Err(format!("{time_format} is not a valid time format").into());
+                }
+                timestamp.format(time_format).to_string()
+            }
+            None => timestamp.to_rfc3339(),
+        };
+        if name.is_empty() || name.contains(std::path::is_separator) {
+            return Err(format!("{name:?} is not a valid file name").into());
+        }
+        Ok(format!("{name}{RESULTS_FILE_SUFFIX}"))
+    }
+}
+
+/// Lists all results files in `results_path`, oldest first. Files are ordered by the RFC 3339
+/// timestamp they are named with, or by when they were last modified if they are named some
+/// other way.
+pub fn list_results_file_paths(results_path: &Path) -> Result<Vec<PathBuf>, Box<dyn error::Error>> {
+    let mut results_file_paths = fs::read_dir(results_path)?
+        .flat_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_str()?;
+            if file_name == BASELINE_RESULTS_FILE_NAME || !entry.file_type().ok()?.is_file() {
+                return None;
+            }
+            let file_name = Compression::ALL
+                .into_iter()
+                .find_map(|compression| file_name.strip_suffix(compression.extension()))
+                .unwrap_or(file_name);
+            let name = file_name.strip_suffix(RESULTS_FILE_SUFFIX)?;
+            let timestamp = match DateTime::parse_from_rfc3339(name) {
+                Ok(timestamp) => timestamp.with_timezone(&Utc),
+                Err(_) => DateTime::<Utc>::from(entry.metadata().ok()?.modified().ok()?),
+            };
+            Some((timestamp, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    results_file_paths.sort();
+    Ok(results_file_paths
+        .into_iter()
+        .map(|(_, results_file_path)| results_file_path)
+        .collect())
+}
+
+/// Path to the results file in `results_path` that was recorded at `timestamp`, named according
+/// to `naming`.
+pub fn results_file_path_at(
+    results_path: &Path,
+    timestamp: &DateTime<Utc>,
+    naming: &ResultsFileNaming,
+) -> Result<PathBuf, Box<dyn error::Error>> {
+    let result_file_name = naming.file_name(timestamp)?;
+    let candidates = std::iter::once(result_file_name.clone()).chain(
+        Compression::ALL
+            .into_iter()
+            .map(|compression| format!("{result_file_name}{}", compression.extension())),
+    );
+    if let Some(result_file_path) = candidates
+        .map(|name| results_path.join(name))
+        .find(|path| path.is_file())
+    {
+        Ok(result_file_path)
+    } else {
+        Err(format!(
+            "no results recorded at {} in {}",
+            timestamp.to_rfc3339(),
+            results_path.display()
+        )
+        .into())
+    }
+}
+
+/// Path to the results file that new results are compared against: the pinned baseline in
+/// `results_path` if there is one, and otherwise the most recent results recorded before
+/// `results_file_path`.
+pub fn baseline_results_file_path(
+    results_path: &Path,
+    results_file_path: &Path,
+) -> Result<Option<PathBuf>, Box<dyn error::Error>> {
+    let baseline_file_path = results_path.join(BASELINE_RESULTS_FILE_NAME);
+    if baseline_file_path.is_file() {
+        return Ok(Some(baseline_file_path));
+    }
+    Ok(list_results_file_paths(results_path)?
+        .into_iter()
+        .rev()
+        .find(|path| path != results_file_path))
+}
+
+/// Pins the results in `results_file_path` as the baseline that later results are compared
+/// against.
+pub fn save_baseline(
+    results_path: &Path,
+    results_file_path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let baseline_file_path = results_path.join(BASELINE_RESULTS_FILE_NAME);
+    // The baseline is always uncompressed, under a fixed name.
+    fs::write(&baseline_file_path, read_results_file(results_file_path)?)?;
+    log::info!(
+        "saved {} as the baseline",
+        results_file_path.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// Prints the average run time of every benchmark on every runner in `results_file_path`,
+/// along with how much it changed from `baseline_file_path`.
+pub fn print_comparison(
+    baseline_file_path: &Path,
+    results_file_path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let baseline = read_results(baseline_file_path)?;
+    let results = read_results(results_file_path)?;
+
+    let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
+    runner_names.sort();
+    let mut runs = results.runs.into_iter().collect::<Vec<_>>();
+    runs.sort_by_key(|(b, _)| b.clone());
+
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            let Some(run) = benchmark_runs.get(runner_name) else {
+                return String::new();
+            };
+            let avg_run_time = stats::summary(run.measured_run_times()).mean;
+            let baseline_avg_run_time = baseline
+                .runs
+                .get(benchmark_name)
+                .and_then(|baseline_runs| baseline_runs.get(runner_name))
+                .map(|baseline_run| stats::summary(baseline_run.measured_run_times()).mean)
+                .filter(|baseline_avg_run_time| !baseline_avg_run_time.is_zero());
+            match baseline_avg_run_time {
+                Some(baseline_avg_run_time) => format!(
+                    "{avg_run_time:?} ({:+.1}%)",
+                    (avg_run_time.as_secs_f64() / baseline_avg_run_time.as_secs_f64() - 1.0)
+                        * 100.0
+                ),
+                None => format!("{avg_run_time:?} (new)"),
+            }
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec![format!(
+        "vs. {}",
+        baseline_file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    )];
+    columns.extend(runner_names);
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Ratio between two runners' run times beyond which one is considered dramatically faster.
+const DRAMATIC_RATIO: f64 = 2.0;
+
+/// A badge in the format of a shields.io endpoint.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    schema_version: u8,
+    label: String,
+    message: String,
+    color: String,
+}
+
+/// Writes a shields.io endpoint badge for every benchmark in `results_file_path` to
+/// `<benchmark>.badge.json` in `badges_path`, showing the runner with the lowest average run
+/// time on it and that time.
+pub fn write_badges(
+    results_file_path: &Path,
+    badges_path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    create_dir_all(badges_path)?;
+
+    let (_, _, runs) = create_run_time_table(&results, TimeFormat::default())?;
+    for (benchmark_name, benchmark_runs) in runs {
+        let Some((runner_name, run_time)) = benchmark_runs
+            .iter()
+            .map(|(runner_name, run)| {
+                let avg_run_time = stats::summary(run.measured_run_times()).mean;
+                (runner_name, avg_run_time)
+            })
+            .min_by_key(|(runner_name, avg_run_time)| (*avg_run_time, *runner_name))
+        else {
+            continue;
+        };
+        let badge = Badge {
+            schema_version: 1,
+            label: benchmark_name.clone(),
+            message: format!("{runner_name} {run_time:.1?}"),
+            color: "green".to_string(),
+        };
+        fs::write(
+            badges_path.join(format!("{benchmark_name}.badge.json")),
+            serde_json::to_string(&badge)?,
+        )?;
+    }
+
+    log::info!("wrote badges to {}", badges_path.display());
+    Ok(())
+}
+
+/// Prints the average run time of every benchmark on just `runner_a` and `runner_b` in
+/// `results_file_path`, with how many times longer `runner_b` took than `runner_a`, sorted by
+/// that ratio. Ratios where one runner is dramatically faster are highlighted.
+pub fn print_runner_comparison(
+    results_file_path: &Path,
+    runner_a: &str,
+    runner_b: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    for runner_name in [runner_a, runner_b] {
+        if !results
+            .runs
+            .values()
+            .any(|benchmark_runs| benchmark_runs.contains_key(runner_name))
+        {
+            return Err(format!(
+                "runner {runner_name} has no runs in {}",
+                results_file_path.display()
+            )
+            .into());
+        }
+    }
+
+    let average_run_time = |benchmark_runs: &HashMap<String, RunResult>, runner_name: &str| {
+        benchmark_runs
+            .get(runner_name)
+            .map(|run| stats::summary(run.measured_run_times()).mean)
+    };
+    let mut rows = results
+        .runs
+        .iter()
+        .filter_map(|(benchmark_name, benchmark_runs)| {
+            let run_time_a = average_run_time(benchmark_runs, runner_a);
+            let run_time_b = average_run_time(benchmark_runs, runner_b);
+            if run_time_a.is_none() && run_time_b.is_none() {
+                return None;
+            }
+            let ratio = run_time_a
+                .zip(run_time_b)
+                .and_then(|(run_time_a, run_time_b)| stats::ratio(run_time_b, run_time_a));
+            Some((benchmark_name, run_time_a, run_time_b, ratio))
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|(name_a, _, _, ratio_a), (name_b, _, _, ratio_b)| {
+        ratio_b
+            .partial_cmp(ratio_a)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| name_a.cmp(name_b))
+    });
+
+    let mut builder = Builder::default();
+    for (benchmark_name, run_time_a, run_time_b, ratio) in rows {
+        let format_run_time = |run_time: Option<Duration>| {
+            run_time
+                .map(|run_time| format!("{run_time:?}"))
+                .unwrap_or_default()
+        };
+        let ratio = match ratio {
+            Some(ratio) if !(1.0 / DRAMATIC_RATIO..=DRAMATIC_RATIO).contains(&ratio) => {
+                format!("**{ratio:.3}x**")
+            }
+            Some(ratio) => format!("{ratio:.3}x"),
+            None => "—".to_string(),
+        };
+        builder.add_record([
+            benchmark_name.clone(),
+            format_run_time(run_time_a),
+            format_run_time(run_time_b),
+            ratio,
+        ]);
+    }
+    builder.set_columns([
+        "".to_string(),
+        runner_a.to_string(),
+        runner_b.to_string(),
+        format!("{runner_b} / {runner_a}"),
+    ]);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    println!("{}", table);
+    Ok(())
+}
+
+/// Path to the most recently recorded results file in `results_path`.
+pub fn latest_results_file_path(results_path: &Path) -> Result<PathBuf, Box<dyn error::Error>> {
+    Ok(list_results_file_paths(results_path)?
+        .pop()
+        .ok_or(format!("no results recorded in {}", results_path.display()))?)
+}
+
+/// A runner whose return data on a benchmark differs from the reference runner's.
+#[derive(Clone, Debug)]
+pub struct Inconsistency {
+    pub benchmark_name: String,
+    pub runner_name: String,
+    pub expected_return_data: String,
+    pub return_data: Option<String>,
+}
+
+/// Checks that every runner returned the same data as `reference_runner_name` on every
+/// benchmark the reference runner ran and reported return data for.
+pub fn verify_consistency(results: &Results, reference_runner_name: &str) -> Vec<Inconsistency> {
+    let mut inconsistencies = Vec::new();
+    for (benchmark, benchmark_results) in results {
+        let Some(expected_return_data) = benchmark_results
+            .iter()
+            .find(|(runner, _)| runner.name == reference_runner_name)
+            .and_then(|(_, result)| result.return_data.as_ref())
+        else {
+            log::warn!(
+                "reference runner {reference_runner_name} reported no return data for benchmark {}",
+                benchmark.name
+            );
+            continue;
+        };
+
+        // Runners that cannot print return data have none to compare.
+        for (runner, result) in benchmark_results
+            .iter()
+            .filter(|(runner, _)| runner.accepts(RunnerOption::PrintReturnData))
+        {
+            if result.return_data.as_ref() != Some(expected_return_data) {
+                inconsistencies.push(Inconsistency {
+                    benchmark_name: benchmark.name.clone(),
+                    runner_name: runner.name.clone(),
+                    expected_return_data: expected_return_data.clone(),
+                    return_data: result.return_data.clone(),
+                });
+            }
+        }
+    }
+    inconsistencies.sort_by(|a, b| {
+        (&a.benchmark_name, &a.runner_name).cmp(&(&b.benchmark_name, &b.runner_name))
+    });
+    inconsistencies
+}
+
+/// Something that kept a benchmark from being run on a runner, or on any runner if it could not
+/// be built.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExitFailure {
+    pub benchmark: String,
+    pub runner: Option<String>,
+    pub reason: String,
+}
+
+/// Counts of what was run and what failed, for scripts that want to know whether everything
+/// succeeded without parsing logs.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExitSummary {
+    pub runners: usize,
+    pub benchmarks: usize,
+    /// Runs of every benchmark on every runner, except those the runner does not support.
+    pub runs_expected: usize,
+    pub runs_succeeded: usize,
+    pub failures: Vec<ExitFailure>,
+    /// `(benchmark, runner)` pairs not run because the runner does not support the benchmark.
+    pub unsupported: Vec<(String, String)>,
+}
+
+impl ExitSummary {
+    /// Summarizes running `built_benchmarks`, including the ones that failed to build, on
+    /// `runners`.
+    pub fn new(
+        built_benchmarks: &BuiltBenchmarks,
+        runners: &[Runner],
+        run_output: &RunOutput,
+    ) -> Self {
+        let benchmarks = built_benchmarks.built.len() + built_benchmarks.failed.len();
+        Self {
+            runners: runners.len(),
+            benchmarks,
+            runs_expected: runners.len() * benchmarks - run_output.unsupported.len(),
+            runs_succeeded: run_output.results.values().map(HashMap::len).sum(),
+            failures: built_benchmarks
+                .failed
+                .iter()
+                .map(|failure| ExitFailure {
+                    benchmark: failure.name.clone(),
+                    runner: None,
+                    reason: failure.reason.clone(),
+                })
+                .chain(run_output.failed.iter().map(|failure| ExitFailure {
+                    benchmark: failure.benchmark_name.clone(),
+                    runner: Some(failure.runner_name.clone()),
+                    reason: failure.reason.clone(),
+                }))
+                .collect(),
+            unsupported: run_output.unsupported.clone(),
+        }
+    }
+
+    /// Whether every benchmark was run on every runner that supports it.
+    pub fn is_complete(&self) -> bool {
+        self.runs_succeeded == self.runs_expected
+    }
+
+    pub fn write(&self, summary_path: &Path) -> Result<(), Box<dyn error::Error>> {
+        fs::write(summary_path, serde_json::to_string_pretty(self)?)?;
+        log::info!("wrote out summary to {}", summary_path.display());
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct BenchmarkManifest {
+    /// Missing if the contract source was not available, e.g. for benchmarks built elsewhere.
+    contract_hash: Option<String>,
+    solc_version: String,
+    #[serde(default)]
+    compiler_version: Option<String>,
+    bytecode_hash: String,
+    calldata_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RunnerManifest {
+    entry_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Manifest {
+    benchmarks: HashMap<String, BenchmarkManifest>,
+    runners: HashMap<String, RunnerManifest>,
+}
+
+fn keccak256_hex(bytes: &[u8]) -> String {
+    hex::encode(Keccak256::digest(bytes))
+}
+
+/// Writes out a manifest of exactly what was benchmarked, as hashes of every benchmark's
+/// contract source, compiled bytecode, and calldata and of every runner's entry point, so that
+/// a later run can be checked to be benchmarking the identical suite.
+pub fn write_manifest(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    manifest_path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    log::debug!("writing manifest out...");
+
+    let manifest = Manifest {
+        benchmarks: benchmarks
+            .iter()
+            .map(|b| {
+                Ok((
+                    b.benchmark.name.clone(),
+                    BenchmarkManifest {
+                        contract_hash: fs::read(&b.benchmark.contract)
+                            .ok()
+                            .map(|contract| keccak256_hex(&contract)),
+                        solc_version: b.benchmark.solc_version.clone(),
+                        compiler_version: b.benchmark.compiler_version.clone(),
+                        bytecode_hash: keccak256_hex(&fs::read(&b.result.contract_bin_path)?),
+                        calldata_hash: keccak256_hex(&b.benchmark.calldata),
+                    },
+                ))
+            })
+            .collect::<Result<_, Box<dyn error::Error>>>()?,
+        runners: runners
+            .iter()
+            .map(|r| {
+                Ok((
+                    r.name.clone(),
+                    RunnerManifest {
+                        entry_hash: runner_entry_hash(r)?,
+                    },
+                ))
+            })
+            .collect::<Result<_, Box<dyn error::Error>>>()?,
+    };
+    fs::write(manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+
+    log::info!("wrote out manifest to {}", manifest_path.display());
+    Ok(())
+}
+
+/// Hash of the runner's entry, or of its image reference if it runs an image.
+fn runner_entry_hash(runner: &Runner) -> Result<String, Box<dyn error::Error>> {
+    match (&runner.entry, &runner.image) {
+        (Some(entry), _) => Ok(keccak256_hex(&fs::read(entry)?)),
+        (None, Some(image)) => Ok(keccak256_hex(image.as_bytes())),
+        (None, None) => Err(format!("runner {} has no entry or image", runner.name).into()),
+    }
+}
+
+#[derive(Serialize)]
+struct ArchivedRunner<'a> {
+    entry_hash: String,
+    #[serde(flatten)]
+    runner: &'a Runner,
+}
+
+/// Archives copies of every benchmark's compiled bytecode and calldata as hex, along with a
+/// `runners.json` describing every runner and the hash of its entry point, in `archive_path`,
+/// so that the results can be audited or reproduced on their own.
+pub fn archive_inputs(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    archive_path: &Path,
+) -> Result<(), Box<dyn error::Error>> {
+    log::debug!("archiving inputs...");
+
+    create_dir_all(archive_path)?;
+    for b in benchmarks {
+        fs::write(
+            archive_path.join(format!("{}.bytecode.hex", b.benchmark.name)),
+            fs::read_to_string(&b.result.contract_bin_path)?.trim(),
+        )?;
+        fs::write(
+            archive_path.join(format!("{}.calldata.hex", b.benchmark.name)),
+            hex::encode(&b.benchmark.calldata),
+        )?;
+    }
+    let archived_runners = runners
+        .iter()
+        .map(|r| {
+            Ok((
+                r.name.clone(),
+                ArchivedRunner {
+                    entry_hash: runner_entry_hash(r)?,
+                    runner: r,
+                },
+            ))
+        })
+        .collect::<Result<HashMap<_, _>, Box<dyn error::Error>>>()?;
+    fs::write(
+        archive_path.join("runners.json"),
+        serde_json::to_string_pretty(&archived_runners)?,
+    )?;
+
+    log::info!("archived inputs to {}", archive_path.display());
+    Ok(())
 }
 
 pub fn record_results(
     results_path: &Path,
     result_file_name: Option<String>,
-    results: &Results,
+    run_output: &RunOutput,
+    failed_builds: &[BuildFailure],
+    skipped_runners: &[SkippedRunner],
+    run_config: &RunConfig,
+    timings: Option<Timings>,
 ) -> Result<PathBuf, Box<dyn error::Error>> {
     log::debug!("writing all results out...");
 
-    create_dir_all(&results_path)?;
+    create_dir_all(results_path)?;
+
+    let results = &run_output.results;
 
     let mut runners = HashSet::<&Runner>::new();
-    for (_, benchmark_results) in results {
-        for (runner, _) in benchmark_results {
+    for benchmark_results in results.values() {
+        for runner in benchmark_results.keys() {
             runners.insert(runner);
         }
     }
@@ -59,17 +843,34 @@ pub fn record_results(
                 )
             })
             .collect(),
+        failed_builds: failed_builds.to_vec(),
+        run_config: run_config.clone(),
+        run_order: run_output.order.clone(),
+        skipped_runs: run_output.skipped.clone(),
+        unsupported_runs: run_output.unsupported.clone(),
+        failed_runs: run_output.failed.clone(),
+        skipped_runners: skipped_runners.to_vec(),
+        run_summary: Some(run_output.summary.clone()),
+        incomplete: run_output.incomplete,
+        timings,
+        stability_checks: run_output.stability_checks.clone(),
     };
 
-    let result_file_path = results_path.join(result_file_name.unwrap_or(format!(
-        "{}.evm-bench.results.json",
-        chrono::offset::Utc::now().to_rfc3339()
-    )));
+    let result_file_path =
+        results_path.join(result_file_name.unwrap_or_else(|| results_file_name(&Utc::now())));
     let mut result_file = fs::OpenOptions::new()
         .create_new(true)
         .write(true)
         .truncate(true)
-        .open(&result_file_path)?;
+        .open(&result_file_path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => format!(
+                "results already exist at {}, refusing to overwrite them",
+                result_file_path.display()
+            )
+            .into(),
+            _ => Box::<dyn error::Error>::from(e),
+        })?;
     write!(
         result_file,
         "{}",
@@ -83,111 +884,1075 @@ pub fn record_results(
     Ok(result_file_path)
 }
 
-pub fn print_results(results_file_path: &Path) -> Result<(), Box<dyn error::Error>> {
+/// Whether the results at `results_file_path` are from a run that was interrupted.
+pub fn results_incomplete(results_file_path: &Path) -> Result<bool, Box<dyn error::Error>> {
+    Ok(read_results(results_file_path)?.incomplete)
+}
+
+fn read_results(results_file_path: &Path) -> Result<ResultsFormatted, Box<dyn error::Error>> {
     log::info!(
         "reading and parsing results from {}...",
         results_file_path.to_string_lossy()
     );
-    let results =
-        serde_json::from_str::<ResultsFormatted>(&fs::read_to_string(results_file_path)?)?;
+    let results = serde_json::from_str::<ResultsFormatted>(&read_results_file(results_file_path)?)?;
     log::debug!(
         "read and parsed results from {}",
         results_file_path.to_string_lossy()
     );
+    Ok(results)
+}
 
-    let mut runner_names: Vec<_> = results.runners.keys().cloned().collect();
-    runner_names.sort();
+/// Runs of every benchmark on every runner, sorted by benchmark name.
+type SortedRuns = Vec<(String, HashMap<String, RunResult>)>;
 
-    let mut runs = results.runs.into_iter().collect::<Vec<_>>();
+/// The numbers behind the table of run times, for tools that want them without parsing the
+/// rendered Markdown. Maps are keyed by runner name, except `cells`, which is keyed by benchmark
+/// name and then runner name.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TableData {
+    /// Runners in the order of the table's columns, from the smallest total to the largest.
+    pub runners: Vec<String>,
+    /// Benchmarks in the order of the table's rows.
+    pub benchmarks: Vec<String>,
+    pub cells: BTreeMap<String, BTreeMap<String, TableCell>>,
+    /// Sum of the mean run times of every benchmark the runner ran.
+    pub totals: BTreeMap<String, Duration>,
+    /// Sum of the mean run times weighted by benchmark weight, if any benchmark has a weight.
+    pub weighted_totals: Option<BTreeMap<String, Duration>>,
+    /// Total relative to the smallest total, or `None` if the smallest total is zero.
+    pub relative: BTreeMap<String, Option<f64>>,
+    /// Geometric mean of the runner's run times relative to the fastest runner's, per benchmark.
+    /// Runners with no run times to compare are left out.
+    pub geomean: BTreeMap<String, f64>,
+    /// Like `geomean`, but weighted by benchmark weight, if any benchmark has a weight.
+    pub weighted_geomean: Option<BTreeMap<String, f64>>,
+}
+
+/// What the table shows for a benchmark on a runner.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableCell {
+    /// Mean run time, over however many of the requested runs could be parsed.
+    Measured {
+        mean: Duration,
+        parsed_runs: u64,
+        requested_runs: u64,
+    },
+    /// The runner does not support the benchmark.
+    Unsupported,
+}
+
+/// Aggregates the results into the numbers shown in the table of run times, along with the runs
+/// they were aggregated from.
+fn aggregate_table(
+    results: &ResultsFormatted,
+) -> Result<(TableData, SortedRuns), Box<dyn error::Error>> {
+    let mut runs = results.runs.clone().into_iter().collect::<Vec<_>>();
     runs.sort_by_key(|(b, _)| b.clone());
 
-    let mut runner_times = HashMap::<String, Vec<Duration>>::new();
-    for (_, benchmark_runs) in runs.iter() {
-        runner_names.iter().for_each(|runner_name| {
-            let run = benchmark_runs.get(runner_name).unwrap();
-            let avg_run_time = run
-                .run_times
-                .iter()
-                .fold(Duration::ZERO, |a, v| a + v.clone())
-                .div_f64(run.run_times.len() as f64);
-            runner_times
-                .entry(runner_name.clone())
-                .or_default()
-                .push(avg_run_time);
-        });
+    let mut cells = BTreeMap::<String, BTreeMap<String, TableCell>>::new();
+    let mut totals = BTreeMap::<String, Duration>::new();
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let benchmark_cells = cells.entry(benchmark_name.clone()).or_default();
+        for (runner_name, run) in benchmark_runs {
+            let mean = stats::summary(run.measured_run_times()).mean;
+            *totals.entry(runner_name.clone()).or_default() += mean;
+            benchmark_cells.insert(
+                runner_name.clone(),
+                TableCell::Measured {
+                    mean,
+                    parsed_runs: run.parsed_runs,
+                    requested_runs: run.requested_runs,
+                },
+            );
+        }
+    }
+    for (benchmark_name, runner_name) in &results.unsupported_runs {
+        if let Some(benchmark_cells) = cells.get_mut(benchmark_name) {
+            benchmark_cells.insert(runner_name.clone(), TableCell::Unsupported);
+        }
     }
 
-    runner_names.sort_by_key(|runner_name| {
-        runner_times
-            .get(runner_name)
-            .unwrap()
-            .into_iter()
-            .fold(Duration::ZERO, |a, v| a + v.clone())
-    });
+    let mut runners: Vec<_> = results.runners.keys().cloned().collect();
+    runners.sort();
+    runners.sort_by_key(|runner_name| totals.get(runner_name).copied().unwrap_or_default());
 
-    let mut builder = Builder::default();
-    let average_runner_times = runner_times
-        .iter()
-        .map(|(name, times)| (name.clone(), times.iter().sum::<Duration>()))
-        .collect::<HashMap<String, Duration>>();
-    let mut record = vec!["**sum**".to_string()];
-    record.extend(
-        runner_names
-            .iter()
-            .map(|runner_name| average_runner_times.get(runner_name))
-            .map(|val| Some(format!("{:?}", val?)))
-            .map(|s| s.unwrap_or_default()),
-    );
-    builder.add_record(record);
-    let min_runner_time = average_runner_times
+    let min_total = *totals
         .values()
         .min()
         .ok_or("could not get minimum runner time")?;
+    if min_total.is_zero() {
+        log::warn!("the fastest runner took no time at all, so relative times are meaningless");
+    }
+    let relative = totals
+        .iter()
+        .map(|(runner_name, total)| (runner_name.clone(), stats::ratio(*total, min_total)))
+        .collect();
+
+    let weights = results
+        .benchmarks
+        .iter()
+        .map(|(name, benchmark)| (name.clone(), benchmark.weight.0))
+        .collect::<HashMap<_, _>>();
+    let weighted = weights.values().any(|weight| *weight != 1.0);
+    let geomeans = |weights: Option<&HashMap<String, f64>>| {
+        runners
+            .iter()
+            .filter_map(|runner_name| {
+                Some((
+                    runner_name.clone(),
+                    geomean_relative_run_time(runner_name, &runs, weights)?,
+                ))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let data = TableData {
+        benchmarks: runs.iter().map(|(name, _)| name.clone()).collect(),
+        cells,
+        weighted_totals: weighted.then(|| {
+            runners
+                .iter()
+                .map(|runner_name| {
+                    (
+                        runner_name.clone(),
+                        weighted_run_time_sum(runner_name, &runs, &weights),
+                    )
+                })
+                .collect()
+        }),
+        relative,
+        geomean: geomeans(None),
+        weighted_geomean: weighted.then(|| geomeans(Some(&weights))),
+        totals,
+        runners,
+    };
+    Ok((data, runs))
+}
+
+/// Reads the results at `results_file_path` and aggregates them into the numbers behind the table
+/// of run times.
+pub fn read_table_data(results_file_path: &Path) -> Result<TableData, Box<dyn error::Error>> {
+    let (data, _) = aggregate_table(&read_results(results_file_path)?)?;
+    Ok(data)
+}
+
+/// Prints the numbers behind the table of run times of the results at `results_file_path` as
+/// JSON, for dashboards and other tools.
+pub fn print_table_json(results_file_path: &Path) -> Result<(), Box<dyn error::Error>> {
+    let data = read_table_data(results_file_path)?;
+    println!("{}", serde_json::to_string_pretty(&data)?);
+    Ok(())
+}
+
+/// Table of the average run time of every benchmark on every runner, with totals, along with the
+/// runner names in the order of its columns and the runs it was made from.
+fn create_run_time_table(
+    results: &ResultsFormatted,
+    time_format: TimeFormat,
+) -> Result<(Table, Vec<String>, SortedRuns), Box<dyn error::Error>> {
+    let (data, runs) = aggregate_table(results)?;
+    let geomean_record = |label: &str, geomeans: &BTreeMap<String, f64>| {
+        let mut record = vec![label.to_string()];
+        record.extend(data.runners.iter().map(|runner_name| {
+            geomeans
+                .get(runner_name)
+                .map(|val| format!("{val:.3?}x"))
+                .unwrap_or_default()
+        }));
+        record
+    };
+    let duration_record = |label: &str, durations: &BTreeMap<String, Duration>| {
+        let mut record = vec![label.to_string()];
+        record.extend(data.runners.iter().map(|runner_name| {
+            durations
+                .get(runner_name)
+                .map(|val| time_format.format(*val))
+                .unwrap_or_default()
+        }));
+        record
+    };
+
+    let mut builder = Builder::default();
+    builder.add_record(duration_record("**sum**", &data.totals));
+    if let Some(weighted_totals) = &data.weighted_totals {
+        builder.add_record(duration_record("**weighted sum**", weighted_totals));
+    }
     let mut record = vec!["**relative**".to_string()];
     record.extend(
-        runner_names
+        data.runners
             .iter()
-            .map(|runner_name| {
-                Some(
-                    average_runner_times.get(runner_name)?.as_secs_f64()
-                        / min_runner_time.as_secs_f64(),
-                )
-            })
-            .map(|val| Some(format!("{:.3?}x", val?)))
-            .map(|s| s.unwrap_or_default()),
+            .map(|runner_name| match data.relative.get(runner_name) {
+                Some(Some(val)) => format!("{val:.3?}x"),
+                Some(None) => "—".to_string(),
+                None => String::new(),
+            }),
     );
     builder.add_record(record);
+    builder.add_record(geomean_record("**geomean**", &data.geomean));
+    if let Some(weighted_geomean) = &data.weighted_geomean {
+        builder.add_record(geomean_record("**weighted geomean**", weighted_geomean));
+    }
 
-    for (benchmark_name, benchmark_runs) in runs.iter() {
-        let vals = runner_names.iter().map(|runner_name| {
-            let run = benchmark_runs.get(runner_name)?;
-            let avg_run_time = run
-                .run_times
-                .iter()
-                .fold(Duration::ZERO, |a, v| a + v.clone())
-                .div_f64(run.run_times.len() as f64);
-            runner_times
-                .entry(runner_name.clone())
-                .or_default()
-                .push(avg_run_time);
-            Some(avg_run_time)
-        });
-
+    for benchmark_name in &data.benchmarks {
         let mut record = vec![benchmark_name.clone()];
-        record.extend(
-            vals.map(|val| Some(format!("{:?}", val?)))
-                .map(|s| s.unwrap_or_default()),
-        );
+        record.extend(data.runners.iter().map(|runner_name| {
+            match data
+                .cells
+                .get(benchmark_name)
+                .and_then(|cells| cells.get(runner_name))
+            {
+                Some(TableCell::Measured {
+                    mean,
+                    parsed_runs,
+                    requested_runs,
+                }) => {
+                    let mean = time_format.format(*mean);
+                    if parsed_runs < requested_runs {
+                        format!("{mean} ({parsed_runs}/{requested_runs})")
+                    } else {
+                        mean
+                    }
+                }
+                Some(TableCell::Unsupported) => "n/a (unsupported)".to_string(),
+                None => String::new(),
+            }
+        }));
         builder.add_record(record);
     }
 
     let mut columns = vec!["".to_owned()];
-    columns.extend(runner_names);
+    columns.extend(data.runners.iter().cloned());
     builder.set_columns(columns);
 
     let mut table = builder.build();
     table.with(Style::markdown());
+    Ok((table, data.runners, runs))
+}
+
+/// Replaces everything between the first two occurrences of `marker` in the file at
+/// `target_path`, e.g. a README, with the table of run times of the results at
+/// `results_file_path`.
+pub fn render_results_table(
+    results_file_path: &Path,
+    target_path: &Path,
+    marker: &str,
+    time_format: TimeFormat,
+) -> Result<(), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    let (table, _, _) = create_run_time_table(&results, time_format)?;
+
+    let target = fs::read_to_string(target_path)?;
+    let markers_not_found = || {
+        format!(
+            "could not find a pair of {marker} markers in {}",
+            target_path.display()
+        )
+    };
+    let start = target.find(marker).ok_or_else(markers_not_found)? + marker.len();
+    let end = start + target[start..].find(marker).ok_or_else(markers_not_found)?;
+    fs::write(
+        target_path,
+        format!("{}\n\n{table}\n\n{}", &target[..start], &target[end..]),
+    )?;
+
+    log::info!(
+        "rendered results from {} into {}",
+        results_file_path.display(),
+        target_path.display()
+    );
+    Ok(())
+}
+
+/// Unit that run times are shown in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum TimeUnit {
+    /// Whichever unit suits each time best
+    #[default]
+    Auto,
+    Us,
+    Ms,
+    S,
+}
+
+/// How run times are shown in the table of run times.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TimeFormat {
+    pub unit: TimeUnit,
+    /// Number of decimal places. By default, automatic units show as many as are significant
+    /// and fixed units show three.
+    pub precision: Option<usize>,
+}
+
+impl TimeFormat {
+    /// Formats `duration` in this format.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use evm_bench::results::{TimeFormat, TimeUnit};
+    ///
+    /// let time = Duration::from_micros(900);
+    /// assert_eq!(TimeFormat::default().format(time), "900µs");
+    /// let format = TimeFormat { unit: TimeUnit::Ms, precision: Some(2) };
+    /// assert_eq!(format.format(time), "0.90ms");
+    /// ```
+    pub fn format(&self, duration: Duration) -> String {
+        let (scale, suffix) = match self.unit {
+            TimeUnit::Auto => {
+                return match self.precision {
+                    Some(precision) => format!("{duration:.precision$?}"),
+                    None => format!("{duration:?}"),
+                }
+            }
+            TimeUnit::Us => (1e6, "µs"),
+            TimeUnit::Ms => (1e3, "ms"),
+            TimeUnit::S => (1.0, "s"),
+        };
+        let precision = self.precision.unwrap_or(3);
+        format!("{:.precision$}{suffix}", duration.as_secs_f64() * scale)
+    }
+}
+
+/// Every `(benchmark, runner)` pair that was meant to be run but has no result, with why, in the
+/// order they were meant to be run.
+fn missing_runs(results: &ResultsFormatted) -> Vec<(String, String, String)> {
+    let mut missing = Vec::new();
+    for (benchmark_name, runner_name) in &results.run_order {
+        if results
+            .runs
+            .get(benchmark_name)
+            .is_some_and(|benchmark_runs| benchmark_runs.contains_key(runner_name))
+            || missing
+                .iter()
+                .any(|(b, r, _)| b == benchmark_name && r == runner_name)
+        {
+            continue;
+        }
+        let pair = (benchmark_name.clone(), runner_name.clone());
+        let reason = match results
+            .failed_runs
+            .iter()
+            .rfind(|f| f.benchmark_name == *benchmark_name && f.runner_name == *runner_name)
+        {
+            Some(failure) => format!("failed ({})", failure.reason),
+            None if results.skipped_runs.contains(&pair) => {
+                "skipped to stay within the time budget".to_string()
+            }
+            None => "never run".to_string(),
+        };
+        missing.push((pair.0, pair.1, reason));
+    }
+    missing
+}
+
+/// Prints the results at `results_file_path` as Markdown tables, followed by notes on anything
+/// that went wrong. With `only_table`, just the table of run times is printed.
+pub fn print_results(
+    results_file_path: &Path,
+    only_table: bool,
+    time_format: TimeFormat,
+) -> Result<(), Box<dyn error::Error>> {
+    let results = read_results(results_file_path)?;
+    let missing_runs = missing_runs(&results);
+    if !only_table && !missing_runs.is_empty() {
+        println!("missing results, the table below is incomplete:");
+        for (benchmark_name, runner_name, reason) in &missing_runs {
+            println!("- benchmark {benchmark_name} on runner {runner_name}: {reason}");
+        }
+        println!();
+    }
+    let (table, runner_names, runs) = create_run_time_table(&results, time_format)?;
     println!("{}", table);
+    if only_table {
+        return Ok(());
+    }
+
+    if let Some(table) = create_memory_table(&runner_names, &runs) {
+        println!();
+        println!("{}", table);
+        if runs
+            .iter()
+            .flat_map(|(_, benchmark_runs)| benchmark_runs.values())
+            .any(|run| run.peak_memory_bytes.is_some() && !run.peak_memory_reported)
+        {
+            println!();
+            println!(
+                "{PROCESS_TREE_MEMORY_MARKER} peak memory of the runner's whole process tree, including any toolchain its entry runs, as it did not report its own"
+            );
+        }
+    }
+
+    if let Some(table) = create_deploy_table(&runner_names, &runs) {
+        println!();
+        println!("{}", table);
+    }
+
+    if let Some(table) = create_throughput_table(&runner_names, &runs) {
+        println!();
+        println!("{}", table);
+    }
+
+    if let Some(table) = create_iterations_table(&runner_names, &runs) {
+        println!();
+        println!("{}", table);
+    }
+
+    if let Some(table) = create_scaling_table(&runner_names, &runs, time_format) {
+        println!();
+        println!("{}", table);
+    }
+
+    if let Some(table) =
+        create_calldata_sweep_table(&results.benchmarks, &runner_names, &runs, time_format)
+    {
+        println!();
+        println!("{}", table);
+    }
+
+    println!();
+    println!("{}", create_stability_table(&runner_names, &runs));
+    let unstable_checks = results
+        .stability_checks
+        .iter()
+        .filter(|check| check.unstable)
+        .collect::<Vec<_>>();
+    if !unstable_checks.is_empty() {
+        println!();
+        for check in unstable_checks {
+            println!(
+                "benchmark {} on runner {} re-measured at {}, {:.1}% off its mean of {}, so the environment may have been unstable",
+                check.benchmark_name,
+                check.runner_name,
+                time_format.format(check.remeasured_mean),
+                check.divergence * 100.0,
+                time_format.format(check.recorded_mean)
+            );
+        }
+    }
+
+    if let Some(table) = create_repetition_table(&runner_names, &runs) {
+        println!();
+        println!("{}", table);
+    }
+
+    if !results.skipped_runs.is_empty() {
+        println!();
+        for (benchmark_name, runner_name) in &results.skipped_runs {
+            println!(
+                "benchmark {benchmark_name} was not run on runner {runner_name} to stay within the time budget"
+            );
+        }
+    }
+
+    if !results.unsupported_runs.is_empty() {
+        println!();
+        for (benchmark_name, runner_name) in &results.unsupported_runs {
+            println!("benchmark {benchmark_name} on runner {runner_name} was skipped: unsupported");
+        }
+    }
+
+    if !results.skipped_runners.is_empty() {
+        println!();
+        for skipped in &results.skipped_runners {
+            println!("runner {} was skipped: {}", skipped.name, skipped.reason);
+        }
+    }
+
+    let nondeterministic_runs = runs
+        .iter()
+        .flat_map(|(benchmark_name, benchmark_runs)| {
+            runner_names.iter().filter_map(move |runner_name| {
+                benchmark_runs
+                    .get(runner_name)?
+                    .nondeterministic
+                    .then_some((benchmark_name, runner_name))
+            })
+        })
+        .collect::<Vec<_>>();
+    if !nondeterministic_runs.is_empty() {
+        println!();
+        for (benchmark_name, runner_name) in nondeterministic_runs {
+            println!(
+                "benchmark {benchmark_name} returned different data across runs on runner {runner_name}"
+            );
+        }
+    }
+
+    if !results.failed_builds.is_empty() {
+        println!();
+        for failure in &results.failed_builds {
+            println!(
+                "benchmark {} failed to build: {}",
+                failure.name, failure.reason
+            );
+        }
+    }
+
+    if !results.failed_runs.is_empty() {
+        println!();
+        for failure in &results.failed_runs {
+            match &failure.stderr_path {
+                Some(stderr_path) => println!(
+                    "benchmark {} failed on runner {}: {} (stderr saved to {})",
+                    failure.benchmark_name,
+                    failure.runner_name,
+                    failure.reason,
+                    stderr_path.display()
+                ),
+                None => println!(
+                    "benchmark {} failed on runner {}: {}",
+                    failure.benchmark_name, failure.runner_name, failure.reason
+                ),
+            }
+        }
+    }
+
+    if results.incomplete {
+        println!();
+        println!("these results are incomplete, the run was interrupted");
+    }
+
+    if let Some(run_summary) = &results.run_summary {
+        println!();
+        print_run_summary(run_summary);
+    }
 
     Ok(())
 }
+
+fn print_run_summary(run_summary: &RunSummary) {
+    println!(
+        "ran {}/{} benchmark runs successfully ({} failed) in {:.2}s",
+        run_summary.succeeded,
+        run_summary.attempted,
+        run_summary.failed,
+        run_summary.total_wall.as_secs_f64()
+    );
+    if let Some((benchmark_name, runner_name, run_time)) = &run_summary.slowest {
+        println!(
+            "slowest run was benchmark {benchmark_name} on runner {runner_name} ({:.2}s)",
+            run_time.as_secs_f64()
+        );
+    }
+}
+
+/// Sum, across every benchmark the runner ran, of the runner's average run time scaled by the
+/// benchmark's weight.
+fn weighted_run_time_sum(
+    runner_name: &str,
+    runs: &[(String, HashMap<String, RunResult>)],
+    weights: &HashMap<String, f64>,
+) -> Duration {
+    runs.iter()
+        .filter_map(|(benchmark_name, benchmark_runs)| {
+            let run = benchmark_runs.get(runner_name)?;
+            let weight = weights.get(benchmark_name).copied().unwrap_or(1.0);
+            Some(
+                stats::summary(run.measured_run_times())
+                    .mean
+                    .mul_f64(weight),
+            )
+        })
+        .sum()
+}
+
+/// Geometric mean, across every benchmark the runner ran, of the runner's average run time
+/// relative to the fastest runner's on that benchmark. Unlike the relative sum, this is not
+/// dominated by whichever benchmarks happen to take the longest. With `weights`, each
+/// benchmark counts in proportion to its weight rather than equally.
+fn geomean_relative_run_time(
+    runner_name: &str,
+    runs: &[(String, HashMap<String, RunResult>)],
+    weights: Option<&HashMap<String, f64>>,
+) -> Option<f64> {
+    let average_run_time =
+        |run: &RunResult| stats::summary(run.measured_run_times()).mean.as_secs_f64();
+
+    let weighted_log_ratios = runs
+        .iter()
+        .filter_map(|(benchmark_name, benchmark_runs)| {
+            let run_time = average_run_time(benchmark_runs.get(runner_name)?);
+            let min_run_time = benchmark_runs
+                .values()
+                .map(average_run_time)
+                .fold(f64::INFINITY, f64::min);
+            let weight = weights
+                .and_then(|weights| weights.get(benchmark_name).copied())
+                .unwrap_or(1.0);
+            (min_run_time > 0.0).then(|| (weight, (run_time / min_run_time).ln()))
+        })
+        .collect::<Vec<_>>();
+    let total_weight = weighted_log_ratios.iter().map(|(w, _)| w).sum::<f64>();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    Some((weighted_log_ratios.iter().map(|(w, r)| w * r).sum::<f64>() / total_weight).exp())
+}
+
+/// Marks memory usage measured over the whole process tree of a runner's entry.
+const PROCESS_TREE_MEMORY_MARKER: &str = "*";
+
+fn format_memory(bytes: u64) -> String {
+    format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// How a run's mean run time varied across repetitions of the suite.
+#[derive(Clone, Copy, Debug)]
+pub struct RepetitionAggregate {
+    /// Mean of the mean run times of each repetition.
+    pub mean_of_means: Duration,
+    /// Sample standard deviation of the mean run times of each repetition, i.e. the square root
+    /// of the between-repetition variance.
+    pub between_repetition_stddev: Duration,
+}
+
+/// Aggregates a run across the repetitions of the suite it was run in, or `None` if the suite
+/// was not repeated.
+pub fn aggregate_repetitions(run: &RunResult) -> Option<RepetitionAggregate> {
+    if run.repetitions.len() < 2 {
+        return None;
+    }
+    let means = run
+        .repetitions
+        .iter()
+        .map(|r| stats::summary(r.measured_run_times()).mean)
+        .collect::<Vec<_>>();
+    let summary = stats::summary(&means);
+    Some(RepetitionAggregate {
+        mean_of_means: summary.mean,
+        between_repetition_stddev: summary.stddev,
+    })
+}
+
+/// Builds a table of each runner's mean run time on each benchmark across repetitions of the
+/// suite, with runners in the same order as `runner_names`. Returns `None` if the suite was not
+/// repeated.
+fn create_repetition_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Option<Table> {
+    if !runs
+        .iter()
+        .flat_map(|(_, benchmark_runs)| benchmark_runs.values())
+        .any(|run| aggregate_repetitions(run).is_some())
+    {
+        return None;
+    }
+
+    let mut builder = Builder::default();
+
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .and_then(aggregate_repetitions)
+                .map(|aggregate| {
+                    format!(
+                        "{:?} ± {:?}",
+                        aggregate.mean_of_means, aggregate.between_repetition_stddev
+                    )
+                })
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["mean across repetitions".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Gas used per microsecond of mean run time, or `None` if the runner did not report gas used.
+fn throughput(run: &RunResult) -> Option<f64> {
+    let mean = stats::summary(run.measured_run_times()).mean;
+    if mean.is_zero() {
+        return None;
+    }
+    Some(run.gas_used? as f64 / (mean.as_secs_f64() * 1e6))
+}
+
+/// Builds a table of the EVM throughput, in gas per microsecond, of each runner on each
+/// benchmark, with runners in the same order as `runner_names`. The aggregate row divides the
+/// total gas by the total mean run time over benchmarks the runner reported gas for. Returns
+/// `None` if no run reported gas used.
+fn create_throughput_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Option<Table> {
+    if !runs
+        .iter()
+        .flat_map(|(_, benchmark_runs)| benchmark_runs.values())
+        .any(|run| throughput(run).is_some())
+    {
+        return None;
+    }
+
+    let mut builder = Builder::default();
+
+    let mut record = vec!["**aggregate**".to_string()];
+    record.extend(runner_names.iter().map(|runner_name| {
+        let (gas, time) = runs
+            .iter()
+            .filter_map(|(_, benchmark_runs)| benchmark_runs.get(runner_name))
+            .filter(|run| throughput(run).is_some())
+            .fold((0.0, 0.0), |(gas, time), run| {
+                (
+                    gas + run.gas_used.unwrap_or_default() as f64,
+                    time + stats::summary(run.measured_run_times()).mean.as_secs_f64() * 1e6,
+                )
+            });
+        if time > 0.0 {
+            format!("{:.1} gas/µs", gas / time)
+        } else {
+            String::new()
+        }
+    }));
+    builder.add_record(record);
+
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .and_then(throughput)
+                .map(|throughput| format!("{throughput:.1} gas/µs"))
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["throughput".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Mean-median divergence above which a run is flagged as unstable in the stability table.
+const UNSTABLE_DIVERGENCE: f64 = 0.1;
+
+/// Builds a table of how far each runner's mean run time on each benchmark diverges from the
+/// median, flagging divergences above [`UNSTABLE_DIVERGENCE`], with runners in the same order
+/// as `runner_names`. Benchmarks with unstable runs may need more runs to be trustworthy.
+fn create_stability_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Table {
+    let mut builder = Builder::default();
+
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .and_then(|run| stats::summary(run.measured_run_times()).mean_median_divergence())
+                .map(|divergence| {
+                    if divergence > UNSTABLE_DIVERGENCE {
+                        format!("{:.1}% (unstable)", divergence * 100.0)
+                    } else {
+                        format!("{:.1}%", divergence * 100.0)
+                    }
+                })
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["mean-median divergence".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    table
+}
+
+/// Builds a table of the peak memory usage of each runner on each benchmark, with runners in
+/// the same order as `runner_names`. Returns `None` if no run recorded its memory usage. Usage
+/// measured over the whole process tree of a runner's entry, because the runner did not report
+/// its own, is marked with [`PROCESS_TREE_MEMORY_MARKER`].
+fn create_memory_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Option<Table> {
+    if !runs
+        .iter()
+        .flat_map(|(_, benchmark_runs)| benchmark_runs.values())
+        .any(|run| run.peak_memory_bytes.is_some())
+    {
+        return None;
+    }
+
+    let format_run_memory = |run: &RunResult| {
+        let bytes = run.peak_memory_bytes?;
+        let marker = if run.peak_memory_reported {
+            ""
+        } else {
+            PROCESS_TREE_MEMORY_MARKER
+        };
+        Some(format!("{}{marker}", format_memory(bytes)))
+    };
+
+    let mut builder = Builder::default();
+
+    let mut record = vec!["**max**".to_string()];
+    record.extend(runner_names.iter().map(|runner_name| {
+        runs.iter()
+            .filter_map(|(_, benchmark_runs)| benchmark_runs.get(runner_name))
+            .max_by_key(|run| run.peak_memory_bytes)
+            .and_then(format_run_memory)
+            .unwrap_or_default()
+    }));
+    builder.add_record(record);
+
+    for (benchmark_name, benchmark_runs) in runs.iter() {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .and_then(format_run_memory)
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["peak memory".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Table of the calls per second of every throughput benchmark, or `None` if there are none.
+fn create_iterations_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Option<Table> {
+    let throughput_runs = runs
+        .iter()
+        .filter(|(_, benchmark_runs)| {
+            benchmark_runs
+                .values()
+                .any(|run| run.iterations_per_sec.is_some())
+        })
+        .collect::<Vec<_>>();
+    if throughput_runs.is_empty() {
+        return None;
+    }
+
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in throughput_runs {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .and_then(|run| run.iterations_per_sec)
+                .map(|rate| format!("{rate:.0} calls/s"))
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["calls per second".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Table of the mean run time of every benchmark expanded from a calldata sweep, by calldata
+/// size, so that each swept benchmark reads as a size-vs-time curve. Returns `None` if no
+/// benchmark was swept.
+fn create_calldata_sweep_table(
+    benchmarks: &HashMap<String, Benchmark>,
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+    time_format: TimeFormat,
+) -> Option<Table> {
+    let mut sweep_runs = runs
+        .iter()
+        .filter_map(|(benchmark_name, benchmark_runs)| {
+            let size = benchmarks.get(benchmark_name)?.calldata_sweep_size?;
+            let (swept_name, _) = benchmark_name.rsplit_once('@')?;
+            Some((swept_name, size, benchmark_runs))
+        })
+        .collect::<Vec<_>>();
+    if sweep_runs.is_empty() {
+        return None;
+    }
+    sweep_runs.sort_by_key(|(swept_name, size, _)| (*swept_name, *size));
+
+    let mut builder = Builder::default();
+    for (swept_name, size, benchmark_runs) in sweep_runs {
+        let mut record = vec![swept_name.to_string(), size.to_string()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .map(|run| time_format.format(stats::summary(run.measured_run_times()).mean))
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["calldata sweep".to_owned(), "bytes".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Table of the mean run time of every benchmark at each scaling run count, or `None` if no
+/// scaling was measured.
+fn create_scaling_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+    time_format: TimeFormat,
+) -> Option<Table> {
+    let scaling_runs = runs
+        .iter()
+        .filter(|(_, benchmark_runs)| benchmark_runs.values().any(|run| !run.scaling.is_empty()))
+        .collect::<Vec<_>>();
+    if scaling_runs.is_empty() {
+        return None;
+    }
+
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in scaling_runs {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .map(|run| {
+                    run.scaling
+                        .iter()
+                        .map(|point| {
+                            format!(
+                                "{}: {}",
+                                point.num_runs,
+                                time_format.format(point.mean_run_time)
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["runs: mean run time".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+/// Table of the average deploy time of every benchmark that timed deployment alongside calls,
+/// or `None` if there are none.
+fn create_deploy_table(
+    runner_names: &[String],
+    runs: &[(String, HashMap<String, RunResult>)],
+) -> Option<Table> {
+    let deploy_runs = runs
+        .iter()
+        .filter(|(_, benchmark_runs)| {
+            benchmark_runs
+                .values()
+                .any(|run| !run.deploy_times.is_empty())
+        })
+        .collect::<Vec<_>>();
+    if deploy_runs.is_empty() {
+        return None;
+    }
+
+    let mut builder = Builder::default();
+    for (benchmark_name, benchmark_runs) in deploy_runs {
+        let mut record = vec![benchmark_name.clone()];
+        record.extend(runner_names.iter().map(|runner_name| {
+            benchmark_runs
+                .get(runner_name)
+                .filter(|run| !run.deploy_times.is_empty())
+                .map(|run| format!("{:?}", stats::summary(&run.deploy_times).mean))
+                .unwrap_or_default()
+        }));
+        builder.add_record(record);
+    }
+
+    let mut columns = vec!["deploy time".to_owned()];
+    columns.extend(runner_names.iter().cloned());
+    builder.set_columns(columns);
+
+    let mut table = builder.build();
+    table.with(Style::markdown());
+    Some(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn run_time_table_has_no_nan_for_runner_that_took_no_time() {
+        let runs = |nanos: u32| {
+            json!({
+                "run_times": [
+                    {"secs": 0, "nanos": nanos},
+                    {"secs": 0, "nanos": nanos},
+                    {"secs": 0, "nanos": nanos},
+                ],
+                "peak_memory_bytes": null,
+                "requested_runs": 3,
+                "parsed_runs": 3,
+            })
+        };
+        let results: ResultsFormatted = serde_json::from_value(json!({
+            "benchmarks": {},
+            "runners": {"slow": {"name": "slow"}, "instant": {"name": "instant"}},
+            "runs": {
+                "a": {"slow": runs(1_000), "instant": runs(0)},
+                "b": {"slow": runs(2_000), "instant": runs(0)},
+            },
+        }))
+        .unwrap();
+
+        let (data, _) = aggregate_table(&results).unwrap();
+        assert!(data.relative.values().all(Option::is_none));
+        assert!(data.geomean.is_empty());
+
+        let (table, runner_names, _) =
+            create_run_time_table(&results, TimeFormat::default()).unwrap();
+        assert_eq!(runner_names, ["instant", "slow"]);
+        let table = table.to_string();
+        assert!(!table.contains("NaN"), "{table}");
+        assert!(!table.contains("inf"), "{table}");
+        let row = |label: &str| {
+            table
+                .lines()
+                .find(|line| line.contains(label))
+                .unwrap()
+                .split('|')
+                .skip(2)
+                .map(str::trim)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row("**relative**")[..2], ["—", "—"]);
+        assert_eq!(row("**geomean**")[..2], ["", ""]);
+    }
+}
@@ -1,29 +1,515 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     error,
-    process::Command,
-    time::Duration,
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    time::{Duration, Instant},
 };
 
+use clap::ValueEnum;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use users::{get_current_gid, get_current_uid};
 
 use crate::{
-    build::BuiltBenchmark,
-    metadata::{Benchmark, Runner},
+    build::{docker_succeeds, BuildFailure, BuildResult, BuiltBenchmark, DOCKER_LABEL},
+    error::EvmBenchError,
+    exec::{interrupted, output_with_peak_memory},
+    host::SkippedRunner,
+    metadata::{
+        Benchmark, BenchmarkCost, BenchmarkMode, BenchmarkWeight, EvmConfig, Runner, RunnerOption,
+    },
+    progress::{Progress, ProgressState},
+    stats::{self, Summary},
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RunResult {
     pub run_times: Vec<Duration>,
+    pub peak_memory_bytes: Option<u64>,
+    /// Whether the runner reported its peak memory usage itself, rather than it being measured
+    /// over the whole process tree of its entry, which includes any toolchain the entry runs.
+    #[serde(default)]
+    pub peak_memory_reported: bool,
+    /// Number of runs the runner was asked to do.
+    #[serde(default)]
+    pub requested_runs: u64,
+    /// Number of run times that could actually be parsed from the runner's output.
+    #[serde(default)]
+    pub parsed_runs: u64,
+    /// Whether the first run time is excluded from statistics as a cold start.
+    #[serde(default)]
+    pub dropped_first_run: bool,
+    /// Summary statistics of the measured run times.
+    #[serde(default)]
+    pub summary: Summary,
+    /// Hex of the return data of the last call, if it was asked for and the runner reported it.
+    #[serde(default)]
+    pub return_data: Option<String>,
+    /// Number of runs the runner skipped for exceeding the maximum run time.
+    #[serde(default)]
+    pub skipped_passes: u64,
+    /// Gas used by the last call, if the runner reported it.
+    #[serde(default)]
+    pub gas_used: Option<u64>,
+    /// Deploy times of benchmarks that time both deployment and calls. The run times of
+    /// benchmarks that only time deployment are themselves deploy times.
+    #[serde(default)]
+    pub deploy_times: Vec<Duration>,
+    /// Whether the runner reported different return data across runs, which a deterministic
+    /// benchmark never should.
+    #[serde(default)]
+    pub nondeterministic: bool,
+    /// Number of times each opcode was executed by a call, keyed by opcode name, if profiling was
+    /// asked for and the runner supports it.
+    #[serde(default)]
+    pub opcode_counts: BTreeMap<String, u64>,
+    /// Mean number of calls per second over the measured runs of a throughput benchmark, whose
+    /// run times are the mean time of a call in each run.
+    #[serde(default)]
+    pub iterations_per_sec: Option<f64>,
+    /// Mean run time when running the benchmark for each of the configured scaling run counts,
+    /// if any were configured.
+    #[serde(default)]
+    pub scaling: Vec<ScalingPoint>,
+    /// Every run time, tagged with whether it was a warmup or measured, so that downstream tools
+    /// can decide for themselves which to include.
+    #[serde(default)]
+    pub passes: Vec<Pass>,
+    /// Which repetition of the suite this result is from.
+    #[serde(default)]
+    pub repetition: u32,
+    /// Per-repetition results, if the suite was repeated. This result then combines the
+    /// measured run times of all of them.
+    #[serde(default)]
+    pub repetitions: Vec<RunResult>,
+}
+
+/// Mean run time of a benchmark run for `num_runs` runs in a single runner process, with no run
+/// dropped, so that warmup shows up as a higher mean for low run counts.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ScalingPoint {
+    pub num_runs: u64,
+    pub mean_run_time: Duration,
+}
+
+/// Whether a run was a warmup, excluded from statistics, or measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PassKind {
+    Warmup,
+    Measured,
+}
+
+/// A single run of a benchmark.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Pass {
+    pub micros: f64,
+    pub kind: PassKind,
+}
+
+impl RunResult {
+    /// Whether the runner reported fewer run times than it was asked for.
+    pub fn is_partial(&self) -> bool {
+        self.parsed_runs < self.requested_runs
+    }
+
+    /// Run times that statistics should be computed over, excluding the first run if it was
+    /// dropped as a cold start.
+    pub fn measured_run_times(&self) -> &[Duration] {
+        if self.dropped_first_run {
+            &self.run_times[1..]
+        } else {
+            &self.run_times
+        }
+    }
+
+    /// Keeps only the first `measured_runs` measured run times, along with everything recorded
+    /// per run, and recomputes the statistics over them.
+    fn truncate_measured_runs(&mut self, measured_runs: usize) {
+        self.run_times
+            .truncate(measured_runs + self.dropped_first_run as usize);
+        // Passes of merged repetitions interleave each repetition's warmup with its measured
+        // runs, so a warmup is only kept if measured runs that are kept follow it.
+        let mut measured = 0;
+        self.passes.retain(|pass| match pass.kind {
+            PassKind::Measured => {
+                measured += 1;
+                measured <= measured_runs
+            }
+            PassKind::Warmup => measured < measured_runs,
+        });
+        self.deploy_times.truncate(self.passes.len());
+        self.parsed_runs = self.run_times.len() as u64;
+        self.summary = stats::summary(self.measured_run_times());
+        // The run times of throughput benchmarks are the reciprocals of their iteration rates.
+        if self.iterations_per_sec.is_some() {
+            let run_times = self.measured_run_times();
+            self.iterations_per_sec = (!run_times.is_empty()).then(|| {
+                run_times.iter().map(|t| 1.0 / t.as_secs_f64()).sum::<f64>()
+                    / run_times.len() as f64
+            });
+        }
+    }
+}
+
+const RETURN_DATA_PREFIX: &str = "evm-bench-return-data:";
+const GAS_USED_PREFIX: &str = "evm-bench-gas-used:";
+const PEAK_MEMORY_PREFIX: &str = "evm-bench-peak-memory-bytes:";
+const RETURN_DATA_HASH_PREFIX: &str = "evm-bench-return-data-hash:";
+const DEPLOY_TIME_PREFIX: &str = "evm-bench-deploy-time:";
+const OPCODE_COUNT_PREFIX: &str = "evm-bench-opcode-count:";
+const ITERATIONS_PER_SEC_PREFIX: &str = "evm-bench-iterations-per-sec:";
+/// Printed by runners in place of the time of a run that exceeded the maximum run time.
+const SKIPPED_PASS_SENTINEL: &str = "evm-bench-skipped-pass";
+
+/// A runner that ran but failed, along with what it printed to stderr.
+#[derive(Debug, Error)]
+#[error("{reason}")]
+struct RunnerFailure {
+    reason: String,
+    stderr: String,
+}
+
+/// A single completed run, as appended to the partial results file while running.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PartialRun {
+    pub benchmark_name: String,
+    pub runner_name: String,
+    pub result: RunResult,
+}
+
+/// Appends `run` to the partial results file at `path`, one JSON object per line.
+fn append_partial_run(path: &Path, run: &PartialRun) -> Result<(), Box<dyn error::Error>> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(run)?)?;
+    Ok(())
+}
+
+/// Reads the runs recorded in the partial results file at `path`. A truncated last line, as left
+/// by a crash mid-write, is skipped.
+pub fn read_partial_runs(path: &Path) -> Result<Vec<PartialRun>, Box<dyn error::Error>> {
+    let mut runs = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        match serde_json::from_str(line) {
+            Ok(run) => runs.push(run),
+            Err(e) => log::warn!("could not parse partial run {line:?}: {e}"),
+        }
+    }
+    Ok(runs)
+}
+
+/// A benchmark that could not be run on a runner, and why.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunFailure {
+    pub benchmark_name: String,
+    pub runner_name: String,
+    pub reason: String,
+    /// Where the runner's stderr was saved, if it was.
+    pub stderr_path: Option<PathBuf>,
+}
+
+/// A benchmark re-run on the runner that was fastest on it, to check that its run times hold up.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StabilityCheck {
+    pub benchmark_name: String,
+    pub runner_name: String,
+    pub recorded_mean: Duration,
+    pub remeasured_mean: Duration,
+    /// Relative difference between the two means, as a fraction of the recorded one.
+    pub divergence: f64,
+    /// Whether the divergence is more than the configured maximum.
+    pub unstable: bool,
+}
+
+/// How long running a benchmark on a runner took from the outside, compared to how much of that
+/// the runner measured.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunTiming {
+    pub benchmark_name: String,
+    pub runner_name: String,
+    pub repetition: u32,
+    /// Wall time of the whole runner process, including startup and setup.
+    pub wall_time: Duration,
+    /// Total time the runner measured across every run.
+    pub measured_time: Duration,
+}
+
+impl RunTiming {
+    /// Time spent outside the runner's measured runs.
+    pub fn overhead(&self) -> Duration {
+        self.wall_time.saturating_sub(self.measured_time)
+    }
 }
 
 type BenchmarkResults = HashMap<Runner, RunResult>;
 pub type Results = HashMap<Benchmark, BenchmarkResults>;
 
+/// How runs are ordered when benchmarks are run on runners. Either way, only one run happens
+/// at a time.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunSchedule {
+    /// Run each benchmark on every runner before moving on to the next benchmark, so that every
+    /// runner is measured at roughly the same point in time.
+    #[default]
+    Interleaved,
+    /// Run every benchmark on a runner before moving on to the next runner.
+    Grouped,
+}
+
+/// Knobs for how benchmarks are run on runners.
+///
+/// Construct one with [`RunConfig::builder`]; the default configuration runs every benchmark
+/// exactly as its metadata describes, with no time limit.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RunConfig {
+    pub num_runs_override: Option<u64>,
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub schedule: RunSchedule,
+    pub shuffle_seed: Option<u64>,
+    pub time_budget: Option<Duration>,
+    #[serde(default)]
+    pub capture_return_data: bool,
+    #[serde(default)]
+    pub drop_first_run: bool,
+    #[serde(default)]
+    pub calldata_file: bool,
+    #[serde(default)]
+    pub check_determinism: bool,
+    #[serde(default)]
+    pub profile: bool,
+    #[serde(default)]
+    pub repetitions: Option<u32>,
+    #[serde(default)]
+    pub failures_path: Option<PathBuf>,
+    #[serde(default)]
+    pub max_pass_micros: Option<u64>,
+    #[serde(default)]
+    pub partial_results_path: Option<PathBuf>,
+    #[serde(default)]
+    pub resume: bool,
+    #[serde(default)]
+    pub command_prefix: Vec<String>,
+    #[serde(default)]
+    pub docker_executable: Option<PathBuf>,
+    #[serde(default)]
+    pub container_user: Option<String>,
+    #[serde(default)]
+    pub allow_network: bool,
+    #[serde(skip)]
+    pub deadline: Option<Instant>,
+    #[serde(default)]
+    pub scaling_num_runs: Vec<u64>,
+    #[serde(default)]
+    pub verify_stability: Option<f64>,
+    #[serde(default)]
+    pub equalize_passes: bool,
+}
+
+impl RunConfig {
+    pub fn builder() -> RunConfigBuilder {
+        RunConfigBuilder::new()
+    }
+
+    fn past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RunConfigBuilder {
+    config: RunConfig,
+}
+
+impl RunConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run every benchmark `num_runs` times, regardless of its metadata.
+    pub fn num_runs_override(mut self, num_runs: u64) -> Self {
+        self.config.num_runs_override = Some(num_runs);
+        self
+    }
+
+    /// Kill a runner that takes longer than `timeout` on a single benchmark.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Order runs according to `schedule`.
+    pub fn schedule(mut self, schedule: RunSchedule) -> Self {
+        self.config.schedule = schedule;
+        self
+    }
+
+    /// Shuffle the order of runs deterministically using `seed`, to spread systematic effects
+    /// like thermal throttling evenly across runners. This takes precedence over the schedule.
+    pub fn shuffle_seed(mut self, seed: u64) -> Self {
+        self.config.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Stop starting new runs once they are projected to take the whole run past `time_budget`.
+    /// Projections are based on how long earlier runs took.
+    pub fn time_budget(mut self, time_budget: Duration) -> Self {
+        self.config.time_budget = Some(time_budget);
+        self
+    }
+
+    /// Ask runners to report the return data of their last call.
+    pub fn capture_return_data(mut self, capture_return_data: bool) -> Self {
+        self.config.capture_return_data = capture_return_data;
+        self
+    }
+
+    /// Exclude the first run time of each benchmark on each runner from statistics, since it is
+    /// usually a cold start. This has no effect when there is only one run time.
+    pub fn drop_first_run(mut self, drop_first_run: bool) -> Self {
+        self.config.drop_first_run = drop_first_run;
+        self
+    }
+
+    /// Pass calldata to runners as a path to a file holding its hex, rather than inline on the
+    /// command line, which large calldata could overflow.
+    pub fn calldata_file(mut self, calldata_file: bool) -> Self {
+        self.config.calldata_file = calldata_file;
+        self
+    }
+
+    /// Ask runners to report a hash of the return data of every run, and flag results where the
+    /// hashes differ.
+    pub fn check_determinism(mut self, check_determinism: bool) -> Self {
+        self.config.check_determinism = check_determinism;
+        self
+    }
+
+    /// Truncate the run times of every runner on a benchmark to the fewest any runner reported on
+    /// it, so that every runner's mean is over the same number of runs.
+    pub fn equalize_passes(mut self, equalize_passes: bool) -> Self {
+        self.config.equalize_passes = equalize_passes;
+        self
+    }
+
+    /// Ask runners to count how many times a call executes each opcode. Only some runners
+    /// support this, and the rest fail.
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.config.profile = profile;
+        self
+    }
+
+    /// Run the whole suite `repetitions` times, combining the results of every repetition.
+    pub fn repetitions(mut self, repetitions: u32) -> Self {
+        self.config.repetitions = Some(repetitions);
+        self
+    }
+
+    /// Save the stderr of runners that fail to files in `failures_path`.
+    pub fn failures_path(mut self, failures_path: PathBuf) -> Self {
+        self.config.failures_path = Some(failures_path);
+        self
+    }
+
+    /// Ask runners to skip recording any single run that takes longer than `max_pass_micros`
+    /// microseconds, so that one stalled run does not skew the statistics.
+    pub fn max_pass_micros(mut self, max_pass_micros: u64) -> Self {
+        self.config.max_pass_micros = Some(max_pass_micros);
+        self
+    }
+
+    /// Append every run to the file at `partial_results_path` as soon as it completes, so that
+    /// the results of a long run are not all lost if it dies before finishing.
+    pub fn partial_results_path(mut self, partial_results_path: PathBuf) -> Self {
+        self.config.partial_results_path = Some(partial_results_path);
+        self
+    }
+
+    /// Pick up where an earlier run left off, reusing the runs recorded in the partial results
+    /// file rather than running them again.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.config.resume = resume;
+        self
+    }
+
+    /// Run runner entries through the command `command_prefix`, e.g. `taskset -c 2` to pin them
+    /// to a core, or `perf stat --` to profile them.
+    pub fn command_prefix(mut self, command_prefix: Vec<String>) -> Self {
+        self.config.command_prefix = command_prefix;
+        self
+    }
+
+    /// Run runners that run an image with the Docker executable `docker_executable`, rather than
+    /// `docker`.
+    pub fn docker_executable(mut self, docker_executable: PathBuf) -> Self {
+        self.config.docker_executable = Some(docker_executable);
+        self
+    }
+
+    /// Run the containers of runners that run an image as `container_user`, e.g. `1000:1000`,
+    /// rather than as the current user and group.
+    pub fn container_user(mut self, container_user: String) -> Self {
+        self.config.container_user = Some(container_user);
+        self
+    }
+
+    /// Allow the containers of runners that run an image to access the network, which they run
+    /// without by default, like build containers.
+    pub fn allow_network(mut self, allow_network: bool) -> Self {
+        self.config.allow_network = allow_network;
+        self
+    }
+
+    /// Stop running once `deadline` passes, as a hard ceiling on how long everything takes: no
+    /// more runs are started, and the run in progress is killed. Unlike the time budget, this
+    /// does not rely on projections.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.config.deadline = Some(deadline);
+        self
+    }
+
+    /// Also run every benchmark for each of `scaling_num_runs` runs, recording the mean run time
+    /// at each count, to show how run times amortize as runners warm up. JIT-compiling runners
+    /// show much larger gaps between low and high counts than interpreters.
+    pub fn scaling_num_runs(mut self, scaling_num_runs: Vec<u64>) -> Self {
+        self.config.scaling_num_runs = scaling_num_runs;
+        self
+    }
+
+    /// After running, run every benchmark once more on the runner that was fastest on it, and
+    /// flag it if its new mean run time diverges from the recorded one by more than
+    /// `max_divergence`, as a fraction. Large divergences point at an unstable environment, e.g.
+    /// thermal throttling or contention.
+    pub fn verify_stability(mut self, max_divergence: f64) -> Self {
+        self.config.verify_stability = Some(max_divergence);
+        self
+    }
+
+    pub fn build(self) -> RunConfig {
+        self.config
+    }
+}
+
 fn run_benchmark_on_runner(
     benchmark: &BuiltBenchmark,
     runner: &Runner,
-) -> Result<RunResult, Box<dyn error::Error>> {
+    config: &RunConfig,
+) -> Result<RunResult, EvmBenchError> {
+    let num_runs = config
+        .num_runs_override
+        .unwrap_or(benchmark.benchmark.num_runs);
+
     log::info!(
         "running benchmark {} on runner {}...",
         benchmark.benchmark.name,
@@ -31,7 +517,7 @@ fn run_benchmark_on_runner(
     );
     log::debug!(
         "running {} times using code {} with calldata {}...",
-        benchmark.benchmark.num_runs,
+        num_runs,
         benchmark
             .result
             .contract_bin_path
@@ -41,92 +527,969 @@ fn run_benchmark_on_runner(
         hex::encode(&benchmark.benchmark.calldata),
     );
 
-    let out = Command::new(&runner.entry)
-        .args([
-            "--contract-code-path",
-            &benchmark.result.contract_bin_path.to_string_lossy(),
-        ])
-        .args(["--calldata", &hex::encode(&benchmark.benchmark.calldata)])
-        .args(["--num-runs", &format!("{}", benchmark.benchmark.num_runs)])
-        .output()?;
+    let contract_code_path = benchmark.result.contract_bin_path.to_string_lossy();
+    let calldata = hex::encode(&benchmark.benchmark.calldata);
+    let docker_executable = config
+        .docker_executable
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("docker"));
+    // Killing the Docker client on timeout does not stop the container, so it is named to be
+    // removed explicitly.
+    let container_name = format!(
+        "evm-bench-run-{}-{}",
+        runner.name.replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.',
+            "-"
+        ),
+        process::id()
+    );
+    let mut invocation: Vec<OsString> = match (&runner.entry, &runner.image) {
+        (Some(entry), _) => vec![entry.into()],
+        (None, Some(image)) => {
+            // Everything runners are given to read lives next to the contract code.
+            let build_path = benchmark
+                .result
+                .contract_bin_path
+                .parent()
+                .unwrap_or(Path::new("/"))
+                .to_string_lossy();
+            // Like builds, containers run as the current user by default, so that anything they
+            // write to mounted paths is not owned by root.
+            let container_user = config
+                .container_user
+                .clone()
+                .unwrap_or_else(|| format!("{}:{}", get_current_uid(), get_current_gid()));
+            let mut invocation: Vec<OsString> = vec![
+                docker_executable.clone().into(),
+                "run".into(),
+                "--rm".into(),
+                "--name".into(),
+                (&container_name).into(),
+                "--label".into(),
+                DOCKER_LABEL.into(),
+                "--user".into(),
+                container_user.into(),
+                "-v".into(),
+                format!("{build_path}:{build_path}:ro").into(),
+            ];
+            if !config.allow_network {
+                invocation.push("--network".into());
+                invocation.push("none".into());
+            }
+            for (key, value) in &runner.env {
+                invocation.push("--env".into());
+                invocation.push(format!("{key}={value}").into());
+            }
+            invocation.push(image.into());
+            invocation
+        }
+        (None, None) => {
+            return Err(EvmBenchError::Run(
+                format!("runner {} has no entry or image", runner.name).into(),
+            ))
+        }
+    };
+    invocation.splice(0..0, config.command_prefix.iter().map(OsString::from));
+    let mut command = Command::new(&invocation[0]);
+    command.args(&invocation[1..]);
+    command.envs(&runner.env);
+    // Benchmarks that ask runners to iterate or to time deployment are not run on runners with
+    // argument templates, so their times are per call.
+    let mut inner_iterations = 1;
+    let mut mode = BenchmarkMode::Call;
+    match runner.templated_args(&contract_code_path, &calldata, num_runs) {
+        Some(args) => {
+            command.args(args);
+        }
+        None => {
+            if benchmark.benchmark.inner_iterations > 1 {
+                inner_iterations = benchmark.benchmark.inner_iterations;
+                command.args(["--inner-iterations", &inner_iterations.to_string()]);
+            }
+            if benchmark.benchmark.mode != BenchmarkMode::Call {
+                mode = benchmark.benchmark.mode;
+                command.args(["--mode", mode.runner_arg()]);
+            }
+            if let Some(duration_ms) = benchmark.benchmark.duration_ms {
+                command.args(["--duration-ms", &duration_ms.to_string()]);
+            }
+            command.args(["--contract-code-path", &contract_code_path]);
+            if config.calldata_file && runner.accepts(RunnerOption::CalldataPath) {
+                let calldata_path = benchmark
+                    .result
+                    .contract_bin_path
+                    .with_extension("calldata");
+                fs::write(&calldata_path, &calldata).map_err(|e| EvmBenchError::Run(e.into()))?;
+                command.args(["--calldata-path", &calldata_path.to_string_lossy()]);
+            } else {
+                command.args(["--calldata", &calldata]);
+            }
+            command
+                .args(["--num-runs", &format!("{}", num_runs)])
+                .args(benchmark.benchmark.evm_config.runner_args());
+            if let Some(initial_state) = &benchmark.benchmark.initial_state {
+                let state_path = benchmark
+                    .result
+                    .contract_bin_path
+                    .with_extension("state.json");
+                let state =
+                    serde_json::to_vec(initial_state).map_err(|e| EvmBenchError::Run(e.into()))?;
+                fs::write(&state_path, state).map_err(|e| EvmBenchError::Run(e.into()))?;
+                command.args(["--state-path", &state_path.to_string_lossy()]);
+            }
+            if let Some(setup_calldata) = &benchmark.benchmark.setup_calldata {
+                command.args(["--setup-calldata", &hex::encode(setup_calldata)]);
+            }
+            if config.capture_return_data && runner.accepts(RunnerOption::PrintReturnData) {
+                command.arg("--print-return-data");
+            }
+            if config.check_determinism && runner.accepts(RunnerOption::PrintReturnDataHashes) {
+                command.arg("--print-return-data-hashes");
+            }
+            if config.profile && runner.accepts(RunnerOption::Profile) {
+                command.arg("--profile");
+            }
+            if let Some(max_pass_micros) = config
+                .max_pass_micros
+                .filter(|_| runner.accepts(RunnerOption::MaxPassMicros))
+            {
+                command.args(["--max-pass-micros", &max_pass_micros.to_string()]);
+            }
+        }
+    }
+    // The run in progress is cut short by the deadline too, however long the timeout is.
+    let timeout = match (
+        config.timeout,
+        config
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now())),
+    ) {
+        (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+        (timeout, remaining) => timeout.or(remaining),
+    };
+    let (out, process_tree_peak_memory_bytes) = match output_with_peak_memory(&mut command, timeout)
+    {
+        // The process waited on for an image is the Docker client, whose memory usage says
+        // nothing about the runner's, so none is measured.
+        Ok((output, _)) if runner.image.is_some() => (output, None),
+        Ok(output) => output,
+        Err(e) => {
+            if runner.image.is_some()
+                && !docker_succeeds(&docker_executable, &["rm", "--force", &container_name])
+                    .unwrap_or(false)
+            {
+                log::warn!("could not remove runner container {container_name}");
+            }
+            return Err(EvmBenchError::Run(e));
+        }
+    };
 
     let stdout = String::from_utf8(out.stdout).unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
     log::trace!("stdout: {}", stdout);
-    log::trace!("stderr: {}", String::from_utf8(out.stderr).unwrap());
+    log::trace!("stderr: {}", stderr);
 
     if out.status.success() {
         let mut times: Vec<Duration> = Vec::new();
-        for line in stdout.trim().split("\n") {
-            times.push(Duration::from_millis(
-                str::parse::<f64>(line)?.round() as u64
+        let mut deploy_times: Vec<Duration> = Vec::new();
+        let mut iterations_per_sec: Vec<f64> = Vec::new();
+        let mut skipped_passes = 0;
+        // Runners print only measurements to stdout, and everything else to stderr.
+        let mut unexpected_lines = Vec::new();
+        for line in stdout.trim().split('\n') {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.trim() == SKIPPED_PASS_SENTINEL {
+                skipped_passes += 1;
+                continue;
+            }
+            if let Some(rate) = line.strip_prefix(ITERATIONS_PER_SEC_PREFIX) {
+                match str::parse::<f64>(rate.trim()) {
+                    Ok(rate) if rate.is_finite() && rate > 0.0 => {
+                        match Duration::try_from_secs_f64(1.0 / rate) {
+                            Ok(time) => {
+                                iterations_per_sec.push(rate);
+                                times.push(time);
+                            }
+                            Err(e) => {
+                                log::warn!("ignoring iteration rate {rate} from line {line:?}: {e}")
+                            }
+                        }
+                    }
+                    Ok(rate) => {
+                        log::warn!("ignoring invalid iteration rate {rate} from line {line:?}")
+                    }
+                    Err(e) => log::warn!("could not parse iteration rate from line {line:?}: {e}"),
+                }
+                continue;
+            }
+            let (time, parsed_times) = match line.strip_prefix(DEPLOY_TIME_PREFIX) {
+                Some(time) => (time.trim(), &mut deploy_times),
+                None => (line, &mut times),
+            };
+            match str::parse::<f64>(time) {
+                Ok(time) if time.is_finite() && time >= 0.0 => {
+                    match Duration::try_from_secs_f64(time / 1e3 / inner_iterations as f64) {
+                        Ok(run_time) => parsed_times.push(run_time),
+                        Err(e) => log::warn!("ignoring run time {time} from line {line:?}: {e}"),
+                    }
+                }
+                Ok(time) => log::warn!("ignoring invalid run time {time} from line {line:?}"),
+                Err(_) => unexpected_lines.push(line),
+            }
+        }
+        if let Some(first_line) = unexpected_lines.first() {
+            log::warn!(
+                "runner {} printed {} lines to stdout that are not measurements, e.g. {first_line:?}; runners should print anything but measurements to stderr",
+                runner.name,
+                unexpected_lines.len(),
+            );
+        }
+        if mode == BenchmarkMode::Deploy {
+            times = std::mem::take(&mut deploy_times);
+        }
+        if times.is_empty() {
+            return Err(EvmBenchError::OutputParse(
+                RunnerFailure {
+                    reason: "could not parse any run times".to_string(),
+                    stderr,
+                }
+                .into(),
             ));
         }
+        if skipped_passes > 0 {
+            log::warn!(
+                "benchmark {} on runner {} skipped {skipped_passes} runs that took too long",
+                benchmark.benchmark.name,
+                runner.name,
+            );
+        }
+        if times.len() as u64 + skipped_passes != num_runs {
+            log::warn!(
+                "benchmark {} on runner {} reported {}/{num_runs} run times",
+                benchmark.benchmark.name,
+                runner.name,
+                times.len(),
+            );
+        }
 
+        let return_data_hashes = stderr
+            .lines()
+            .filter_map(|line| line.strip_prefix(RETURN_DATA_HASH_PREFIX))
+            .map(str::trim)
+            .collect::<HashSet<_>>();
+        let nondeterministic = return_data_hashes.len() > 1;
+        if nondeterministic {
+            log::warn!(
+                "benchmark {} on runner {} returned different data across runs",
+                benchmark.benchmark.name,
+                runner.name,
+            );
+        }
+
+        let dropped_first_run = config.drop_first_run && times.len() > 1;
+        let passes = times
+            .iter()
+            .enumerate()
+            .map(|(i, time)| Pass {
+                micros: time.as_secs_f64() * 1e6,
+                kind: if dropped_first_run && i == 0 {
+                    PassKind::Warmup
+                } else {
+                    PassKind::Measured
+                },
+            })
+            .collect();
+        let measured_iterations_per_sec = if dropped_first_run && !iterations_per_sec.is_empty() {
+            &iterations_per_sec[1..]
+        } else {
+            &iterations_per_sec[..]
+        };
+        let summary = if dropped_first_run {
+            stats::summary(&times[1..])
+        } else {
+            stats::summary(&times)
+        };
         log::debug!(
-            "ran benchmark {} on runner {}",
+            "ran benchmark {} on runner {} (mean {:?}, median {:?})",
             benchmark.benchmark.name,
-            runner.name
+            runner.name,
+            summary.mean,
+            summary.median,
         );
-        Ok(RunResult { run_times: times })
+        let reported_peak_memory_bytes = stderr
+            .lines()
+            .rev()
+            .find_map(|line| line.strip_prefix(PEAK_MEMORY_PREFIX))
+            .and_then(|bytes| bytes.trim().parse().ok());
+        Ok(RunResult {
+            requested_runs: num_runs,
+            parsed_runs: times.len() as u64,
+            dropped_first_run,
+            summary,
+            return_data: stderr
+                .lines()
+                .rev()
+                .find_map(|line| line.strip_prefix(RETURN_DATA_PREFIX))
+                .map(|return_data| return_data.trim().to_string()),
+            skipped_passes,
+            gas_used: stderr
+                .lines()
+                .rev()
+                .find_map(|line| line.strip_prefix(GAS_USED_PREFIX))
+                .and_then(|gas_used| gas_used.trim().parse().ok()),
+            nondeterministic,
+            opcode_counts: stderr
+                .lines()
+                .filter_map(|line| line.strip_prefix(OPCODE_COUNT_PREFIX))
+                .filter_map(|count| {
+                    let (opcode, count) = count.trim().split_once(' ')?;
+                    Some((opcode.to_string(), count.trim().parse().ok()?))
+                })
+                .collect(),
+            iterations_per_sec: (!measured_iterations_per_sec.is_empty()).then(|| {
+                measured_iterations_per_sec.iter().sum::<f64>()
+                    / measured_iterations_per_sec.len() as f64
+            }),
+            scaling: Vec::new(),
+            passes,
+            deploy_times,
+            run_times: times,
+            peak_memory_bytes: reported_peak_memory_bytes.or(process_tree_peak_memory_bytes),
+            peak_memory_reported: reported_peak_memory_bytes.is_some(),
+            repetition: 0,
+            repetitions: Vec::new(),
+        })
     } else {
-        Err(format!("{}", out.status).into())
+        Err(EvmBenchError::Run(
+            RunnerFailure {
+                reason: format!("{}", out.status),
+                stderr,
+            }
+            .into(),
+        ))
     }
 }
 
-fn run_benchmark_on_runners(
+/// Writes the stderr of a runner that failed `error` to `failures_path`, if it got far enough to
+/// print any, returning where it was written.
+fn save_failure_stderr(
+    error: &EvmBenchError,
+    failures_path: &Path,
+    file_name: &str,
+) -> Result<Option<PathBuf>, Box<dyn error::Error>> {
+    let failure = match error {
+        EvmBenchError::Run(e) | EvmBenchError::OutputParse(e) => e.downcast_ref::<RunnerFailure>(),
+        _ => None,
+    };
+    let Some(failure) = failure else {
+        return Ok(None);
+    };
+
+    fs::create_dir_all(failures_path)?;
+    let stderr_path = failures_path.join(file_name);
+    fs::write(&stderr_path, &failure.stderr)?;
+    Ok(Some(stderr_path))
+}
+
+/// Runs `benchmark` on `runner` for each of the configured scaling run counts, skipping counts
+/// that fail.
+fn measure_scaling(
     benchmark: &BuiltBenchmark,
-    runners: &Vec<Runner>,
-) -> Result<BenchmarkResults, Box<dyn error::Error>> {
-    let runner_names = runners
+    runner: &Runner,
+    config: &RunConfig,
+) -> Vec<ScalingPoint> {
+    config
+        .scaling_num_runs
         .iter()
-        .map(|b| b.name.clone())
-        .collect::<HashSet<_>>();
+        .filter_map(|&num_runs| {
+            if interrupted() || config.past_deadline() {
+                return None;
+            }
+            let config = RunConfig {
+                num_runs_override: Some(num_runs),
+                drop_first_run: false,
+                ..config.clone()
+            };
+            match run_benchmark_on_runner(benchmark, runner, &config) {
+                Ok(result) => Some(ScalingPoint {
+                    num_runs,
+                    mean_run_time: result.summary.mean,
+                }),
+                Err(e) => {
+                    log::warn!(
+                        "could not run benchmark {} on runner {} for {num_runs} runs: {e}",
+                        benchmark.benchmark.name,
+                        runner.name
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
 
-    log::info!(
-        "running benchmark {} on {} runners...",
-        benchmark.benchmark.name,
-        runners.len()
-    );
-    log::debug!(
-        "runners: {}",
-        runner_names.iter().cloned().collect::<Vec<_>>().join(", ")
-    );
+/// Warns about every benchmark on which runners reported different numbers of run times, which
+/// makes comparing their means slightly apples-to-oranges. With `equalize`, every runner's run
+/// times on such a benchmark are truncated to the fewest any runner reported.
+fn check_pass_counts(results: &mut Results, equalize: bool) {
+    let mut benchmark_results = results.iter_mut().collect::<Vec<_>>();
+    benchmark_results.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    for (benchmark, runner_results) in benchmark_results {
+        let mut pass_counts = runner_results
+            .iter()
+            .map(|(runner, result)| (runner.name.as_str(), result.measured_run_times().len()))
+            .collect::<Vec<_>>();
+        pass_counts.sort();
+        let Some(min_passes) = pass_counts.iter().map(|(_, count)| *count).min() else {
+            continue;
+        };
+        if pass_counts.iter().all(|(_, count)| *count == min_passes) {
+            continue;
+        }
+        log::warn!(
+            "runners reported different numbers of runs of benchmark {}{}: {}",
+            benchmark.name,
+            if equalize {
+                format!(", using the first {min_passes} of each")
+            } else {
+                String::new()
+            },
+            pass_counts
+                .iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if !equalize {
+            continue;
+        }
+        for result in runner_results.values_mut() {
+            result.truncate_measured_runs(min_passes);
+        }
+    }
+}
 
-    let mut results = HashMap::<Runner, RunResult>::new();
-    for runner in runners {
-        let result = match run_benchmark_on_runner(benchmark, runner) {
-            Ok(res) => res,
+/// Combines results of the same benchmark on the same runner from several repetitions of the
+/// suite, keeping each repetition's result for auditing.
+fn merge_repetitions(mut repetitions: Vec<RunResult>) -> RunResult {
+    if repetitions.len() == 1 {
+        return repetitions.pop().unwrap();
+    }
+
+    let run_times = repetitions
+        .iter()
+        .flat_map(|r| r.measured_run_times().iter().copied())
+        .collect::<Vec<_>>();
+    RunResult {
+        requested_runs: repetitions
+            .iter()
+            .map(|r| r.requested_runs - r.dropped_first_run as u64)
+            .sum(),
+        parsed_runs: run_times.len() as u64,
+        dropped_first_run: false,
+        summary: stats::summary(&run_times),
+        return_data: repetitions.last().and_then(|r| r.return_data.clone()),
+        skipped_passes: repetitions.iter().map(|r| r.skipped_passes).sum(),
+        gas_used: repetitions.last().and_then(|r| r.gas_used),
+        nondeterministic: repetitions.iter().any(|r| r.nondeterministic),
+        opcode_counts: repetitions
+            .last()
+            .map(|r| r.opcode_counts.clone())
+            .unwrap_or_default(),
+        iterations_per_sec: {
+            let rates = repetitions
+                .iter()
+                .filter_map(|r| r.iterations_per_sec)
+                .collect::<Vec<_>>();
+            (!rates.is_empty()).then(|| rates.iter().sum::<f64>() / rates.len() as f64)
+        },
+        scaling: repetitions
+            .last()
+            .map(|r| r.scaling.clone())
+            .unwrap_or_default(),
+        passes: repetitions
+            .iter()
+            .flat_map(|r| r.passes.iter().cloned())
+            .collect(),
+        deploy_times: repetitions
+            .iter()
+            .flat_map(|r| r.deploy_times.iter().copied())
+            .collect(),
+        peak_memory_bytes: repetitions.iter().filter_map(|r| r.peak_memory_bytes).max(),
+        peak_memory_reported: repetitions
+            .iter()
+            .filter(|r| r.peak_memory_bytes.is_some())
+            .all(|r| r.peak_memory_reported),
+        run_times,
+        repetition: 0,
+        repetitions,
+    }
+}
+
+/// Re-runs every benchmark in `results` once on the runner with the lowest mean run time on it,
+/// comparing the new mean run time to the recorded one. Benchmarks that cannot be re-run are
+/// left out.
+fn verify_stability(
+    benchmarks: &[BuiltBenchmark],
+    results: &Results,
+    config: &RunConfig,
+    max_divergence: f64,
+) -> Vec<StabilityCheck> {
+    log::info!("verifying stability of {} benchmarks...", results.len());
+    let config = RunConfig {
+        scaling_num_runs: Vec::new(),
+        failures_path: None,
+        partial_results_path: None,
+        ..config.clone()
+    };
+    let mut benchmarks = benchmarks.iter().collect::<Vec<_>>();
+    benchmarks.sort_by(|a, b| a.benchmark.name.cmp(&b.benchmark.name));
+
+    let mut checks = Vec::new();
+    for benchmark in benchmarks {
+        if interrupted() || config.past_deadline() {
+            break;
+        }
+        let Some((runner, recorded_mean)) = results
+            .get(&benchmark.benchmark)
+            .into_iter()
+            .flatten()
+            .map(|(runner, result)| (runner, stats::summary(result.measured_run_times()).mean))
+            .filter(|(_, mean)| !mean.is_zero())
+            .min_by_key(|(_, mean)| *mean)
+        else {
+            continue;
+        };
+        let remeasured_mean = match run_benchmark_on_runner(benchmark, runner, &config) {
+            Ok(result) => stats::summary(result.measured_run_times()).mean,
             Err(e) => {
                 log::warn!(
-                    "could not run benchmark {} on runner {}: {e}",
+                    "could not re-run benchmark {} on runner {} to verify stability: {e}",
                     benchmark.benchmark.name,
                     runner.name
                 );
                 continue;
             }
         };
-        results.insert(runner.clone(), result);
+        let divergence =
+            stats::ratio(remeasured_mean, recorded_mean).map_or(0.0, |ratio| (ratio - 1.0).abs());
+        let unstable = divergence > max_divergence;
+        if unstable {
+            log::warn!(
+                "benchmark {} on runner {} re-measured at {remeasured_mean:?}, {:.1}% off its mean of {recorded_mean:?}, the environment may be unstable",
+                benchmark.benchmark.name,
+                runner.name,
+                divergence * 100.0
+            );
+        }
+        checks.push(StabilityCheck {
+            benchmark_name: benchmark.benchmark.name.clone(),
+            runner_name: runner.name.clone(),
+            recorded_mean,
+            remeasured_mean,
+            divergence,
+            unstable,
+        });
+    }
+    checks
+}
+
+/// Order in which every benchmark is run on every runner: following the configured schedule, or
+/// shuffled deterministically if a seed is configured.
+///
+/// Benchmarks and runners are taken in order of their names, whatever order they are given in,
+/// so that the order is the same from run to run however benchmarks and runners were found.
+/// Benchmarks are left out for runners that do not support them.
+pub fn run_order<'a>(
+    benchmarks: &'a [BuiltBenchmark],
+    runners: &'a [Runner],
+    config: &RunConfig,
+) -> Vec<(&'a BuiltBenchmark, &'a Runner)> {
+    let mut benchmarks = benchmarks.iter().collect::<Vec<_>>();
+    benchmarks.sort_by(|a, b| a.benchmark.name.cmp(&b.benchmark.name));
+    let mut runners = runners.iter().collect::<Vec<_>>();
+    runners.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut order = match config.schedule {
+        RunSchedule::Interleaved => benchmarks
+            .iter()
+            .flat_map(|&benchmark| runners.iter().map(move |&runner| (benchmark, runner)))
+            .collect::<Vec<_>>(),
+        RunSchedule::Grouped => runners
+            .iter()
+            .flat_map(|&runner| benchmarks.iter().map(move |&benchmark| (benchmark, runner)))
+            .collect::<Vec<_>>(),
+    };
+    order.retain(|(benchmark, runner)| runner.supports(&benchmark.benchmark));
+    if let Some(seed) = config.shuffle_seed {
+        order.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+    }
+    order
+}
+
+/// Overall statistics of running benchmarks on runners, as a quick health check.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RunSummary {
+    /// Wall-clock time spent running, including runner process startup.
+    pub total_wall: Duration,
+    pub attempted: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// `(benchmark, runner)` pair whose run took the longest, and how long it took.
+    pub slowest: Option<(String, String, Duration)>,
+}
+
+/// Everything that came out of running benchmarks on runners.
+#[derive(Clone, Debug, Default)]
+pub struct RunOutput {
+    pub results: Results,
+    /// `(benchmark, runner)` pairs in the order they were run.
+    pub order: Vec<(String, String)>,
+    /// `(benchmark, runner)` pairs that were not run because they would have exceeded the time
+    /// budget.
+    pub skipped: Vec<(String, String)>,
+    /// `(benchmark, runner)` pairs that were not run because the runner does not support the
+    /// benchmark.
+    pub unsupported: Vec<(String, String)>,
+    pub failed: Vec<RunFailure>,
+    pub summary: RunSummary,
+    /// Whether running was interrupted before every run was done.
+    pub incomplete: bool,
+    /// Whether running stopped early because the deadline passed.
+    pub deadline_exceeded: bool,
+    /// Wall and measured time of every successful run.
+    pub timings: Vec<RunTiming>,
+    /// Re-runs made to verify stability, if configured.
+    pub stability_checks: Vec<StabilityCheck>,
+}
+
+/// Estimates how long running `benchmark` `num_runs` times will take from how long previous runs
+/// took: runs of the same benchmark on other runners if there were any, and the time per run of
+/// all previous runs otherwise. Before anything has run, the estimate is seeded from the
+/// benchmark's cost, or a moderate cost if it has none.
+fn estimate_run_time(
+    benchmark: &Benchmark,
+    num_runs: u64,
+    run_times: &[(String, u64, Duration)],
+) -> Duration {
+    let same_benchmark = run_times
+        .iter()
+        .filter(|(b, _, _)| *b == benchmark.name)
+        .map(|(_, _, t)| *t)
+        .collect::<Vec<_>>();
+    if !same_benchmark.is_empty() {
+        return stats::summary(&same_benchmark).mean;
+    }
+    let per_run = if run_times.is_empty() {
+        benchmark
+            .cost
+            .unwrap_or(BenchmarkCost::Moderate)
+            .run_time_prior()
+    } else {
+        stats::summary(
+            &run_times
+                .iter()
+                .map(|(_, runs, t)| *t / (*runs).max(1) as u32)
+                .collect::<Vec<_>>(),
+        )
+        .mean
+    };
+    per_run * num_runs as u32
+}
+
+/// Init code of the health check contract, which deploys a contract with no code, so that calling
+/// it does nothing at all.
+const HEALTH_CHECK_BYTECODE: &str = "60006000f3";
+
+/// Runs a trivial benchmark once on `runner`, to check that it implements the runner interface
+/// with the options `config` would have it run with, and prints run times that can be parsed.
+/// The benchmark is written to `scratch_path`.
+pub fn health_check(
+    runner: &Runner,
+    config: &RunConfig,
+    scratch_path: &Path,
+) -> Result<(), EvmBenchError> {
+    run_bytecode(
+        "health-check",
+        HEALTH_CHECK_BYTECODE,
+        &[],
+        1,
+        runner,
+        config,
+        scratch_path,
+    )
+    .map(|_| ())
+}
+
+/// Runs init code `bytecode`, as hex, on `runner` as a one-off benchmark named `name`, calling
+/// the contract it deploys with `calldata` `num_runs` times, without any benchmark metadata or
+/// compiling anything. The bytecode is written to `scratch_path`.
+pub fn run_bytecode(
+    name: &str,
+    bytecode: &str,
+    calldata: &[u8],
+    num_runs: u64,
+    runner: &Runner,
+    config: &RunConfig,
+    scratch_path: &Path,
+) -> Result<RunResult, EvmBenchError> {
+    let build_path = scratch_path.join(name);
+    fs::create_dir_all(&build_path).map_err(|e| EvmBenchError::Run(e.into()))?;
+    let contract_bin_path = build_path.join(format!("{name}.bin"));
+    fs::write(&contract_bin_path, bytecode).map_err(|e| EvmBenchError::Run(e.into()))?;
+    let benchmark = BuiltBenchmark {
+        benchmark: Benchmark {
+            name: name.to_string(),
+            solc_version: String::new(),
+            num_runs,
+            cost: None,
+            contract: build_path.join(format!("{name}.sol")),
+            build_context: build_path,
+            calldata: calldata.to_vec(),
+            evm_config: EvmConfig::default(),
+            inner_iterations: 1,
+            weight: BenchmarkWeight::default(),
+            mode: BenchmarkMode::Call,
+            duration_ms: None,
+            initial_state: None,
+            compiler_version: None,
+            calldata_sweep: None,
+            calldata_sweep_size: None,
+            setup_calldata: None,
+        },
+        result: BuildResult {
+            contract_bin_path,
+            bytecode_hash: String::new(),
+            warnings: Vec::new(),
+            build_time: Duration::ZERO,
+        },
+    };
+    let config = RunConfig {
+        num_runs_override: Some(num_runs),
+        drop_first_run: false,
+        scaling_num_runs: Vec::new(),
+        failures_path: None,
+        partial_results_path: None,
+        ..config.clone()
+    };
+    run_benchmark_on_runner(&benchmark, runner, &config)
+}
+
+/// Health checks every runner, returning the runners that passed along with those that did not
+/// and why, so that runners that do not implement the runner interface properly are found
+/// before any time is spent running benchmarks on them.
+pub fn check_runner_health(
+    runners: Vec<Runner>,
+    config: &RunConfig,
+    scratch_path: &Path,
+) -> (Vec<Runner>, Vec<SkippedRunner>) {
+    log::info!("health checking {} runners...", runners.len());
+    let mut healthy = Vec::new();
+    let mut unhealthy = Vec::new();
+    for runner in runners {
+        match health_check(&runner, config, scratch_path) {
+            Ok(()) => healthy.push(runner),
+            Err(e) => {
+                log::error!("runner {} failed its health check: {e}", runner.name);
+                unhealthy.push(SkippedRunner {
+                    name: runner.name,
+                    reason: format!("failed its health check: {e}"),
+                });
+            }
+        }
+    }
+    (healthy, unhealthy)
+}
+
+/// Starts every runner that runs an image once on a contract that does nothing, so that the
+/// first benchmark on each does not pay for a cold container start. Runners that fail are only
+/// logged, as their benchmarks fail with more context later.
+pub fn warm_runner_images(runners: &[Runner], config: &RunConfig, scratch_path: &Path) {
+    let image_runners = runners
+        .iter()
+        .filter(|r| r.image.is_some())
+        .collect::<Vec<_>>();
+    if image_runners.is_empty() {
+        return;
+    }
+    log::info!("warming {} runner images...", image_runners.len());
+    for runner in image_runners {
+        if let Err(e) = health_check(runner, config, scratch_path) {
+            log::warn!("could not warm image of runner {}: {e}", runner.name);
+        }
+    }
+}
+
+/// Runs every benchmark once on `runner`, returning the benchmarks that could be deployed and
+/// called along with those that could not, so that broken benchmarks are found before spending
+/// time running them on every runner. Benchmarks `runner` does not support are returned
+/// unchecked, among those that could be.
+pub fn preflight_benchmarks<'a>(
+    benchmarks: &'a [BuiltBenchmark],
+    runner: &Runner,
+    config: &RunConfig,
+) -> (Vec<&'a BuiltBenchmark>, Vec<BuildFailure>) {
+    log::info!(
+        "checking {} benchmarks on runner {}...",
+        benchmarks.len(),
+        runner.name
+    );
+    let config = RunConfig {
+        num_runs_override: Some(1),
+        ..config.clone()
+    };
+
+    let mut passed = Vec::new();
+    let mut failed = Vec::new();
+    for benchmark in benchmarks {
+        if interrupted() {
+            log::warn!("interrupted, not checking any more benchmarks");
+            break;
+        }
+        if !runner.supports(&benchmark.benchmark) {
+            log::debug!(
+                "not checking benchmark {}, runner {} does not support it",
+                benchmark.benchmark.name,
+                runner.name
+            );
+            passed.push(benchmark);
+            continue;
+        }
+        match run_benchmark_on_runner(benchmark, runner, &config) {
+            Ok(_) => passed.push(benchmark),
+            Err(e) => {
+                log::error!(
+                    "benchmark {} failed its check on runner {}: {e}",
+                    benchmark.benchmark.name,
+                    runner.name
+                );
+                failed.push(BuildFailure {
+                    name: benchmark.benchmark.name.clone(),
+                    reason: format!("failed its check on runner {}: {e}", runner.name),
+                });
+            }
+        }
     }
 
     log::debug!(
-        "ran benchmark {} on {} runners ({} successful)",
-        benchmark.benchmark.name,
-        runners.len(),
-        results.len()
+        "checked {} benchmarks ({} passed)",
+        benchmarks.len(),
+        passed.len()
     );
-    Ok(results)
+    (passed, failed)
 }
 
+/// Runs every benchmark on every runner, in a deterministic order by name (see [`run_order`]).
 pub fn run_benchmarks_on_runners(
-    benchmarks: &Vec<BuiltBenchmark>,
-    runners: &Vec<Runner>,
-) -> Result<Results, Box<dyn error::Error>> {
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+) -> Result<Results, EvmBenchError> {
+    Ok(run_benchmarks_on_runners_with_config(benchmarks, runners, &RunConfig::default())?.results)
+}
+
+pub fn run_benchmarks_on_runners_with_config(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    config: &RunConfig,
+) -> Result<RunOutput, EvmBenchError> {
+    run_benchmarks_on_runners_with_progress(benchmarks, runners, config, &mut |_| {})
+}
+
+/// A finished run of a benchmark on a runner, as yielded by [`RunStream`].
+#[derive(Debug)]
+pub struct StreamedRun<'a> {
+    pub benchmark: &'a BuiltBenchmark,
+    pub runner: &'a Runner,
+    pub repetition: u32,
+    pub result: Result<RunResult, EvmBenchError>,
+}
+
+/// Runs every benchmark on every runner in the configured order, one run each time it is
+/// advanced, without holding on to any results. Made with [`stream_benchmarks_on_runners`].
+pub struct RunStream<'a> {
+    order: Vec<(&'a BuiltBenchmark, &'a Runner)>,
+    config: &'a RunConfig,
+    num_repetitions: u32,
+    next: usize,
+}
+
+impl<'a> Iterator for RunStream<'a> {
+    type Item = StreamedRun<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.order.len() * self.num_repetitions as usize {
+            return None;
+        }
+        if interrupted() {
+            log::warn!("interrupted, not starting any more runs");
+            return None;
+        }
+        let (benchmark, runner) = self.order[self.next % self.order.len()];
+        let repetition = (self.next / self.order.len()) as u32;
+        self.next += 1;
+        let result =
+            run_benchmark_on_runner(benchmark, runner, self.config).map(|result| RunResult {
+                repetition,
+                ..result
+            });
+        Some(StreamedRun {
+            benchmark,
+            runner,
+            repetition,
+            result,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.order.len() * self.num_repetitions as usize - self.next;
+        (0, Some(remaining))
+    }
+}
+
+/// Runs every benchmark on every runner lazily, yielding each run as it finishes so that very
+/// large suites can be processed, e.g. written to disk, in bounded memory. Unlike
+/// [`run_benchmarks_on_runners_with_config`], repetitions are yielded separately rather than
+/// merged, and the time budget, partial results, and failure logs are left to the caller.
+pub fn stream_benchmarks_on_runners<'a>(
+    benchmarks: &'a [BuiltBenchmark],
+    runners: &'a [Runner],
+    config: &'a RunConfig,
+) -> RunStream<'a> {
+    RunStream {
+        order: run_order(benchmarks, runners, config),
+        config,
+        num_repetitions: config.repetitions.unwrap_or(1).max(1),
+        next: 0,
+    }
+}
+
+/// Progress of a benchmark being run on a runner.
+pub type RunProgress<'a> = Progress<(&'a BuiltBenchmark, &'a Runner)>;
+
+/// Runs every benchmark on every runner like [`run_benchmarks_on_runners_with_config`], calling
+/// `on_progress` as each run starts and finishes. Runs carried over from partial results or
+/// skipped for the time budget finish without starting.
+pub fn run_benchmarks_on_runners_with_progress(
+    benchmarks: &[BuiltBenchmark],
+    runners: &[Runner],
+    config: &RunConfig,
+    on_progress: &mut dyn FnMut(RunProgress),
+) -> Result<RunOutput, EvmBenchError> {
     let benchmark_names = benchmarks
         .iter()
         .map(|b| b.benchmark.name.clone())
         .collect::<HashSet<_>>();
+    let runner_names = runners
+        .iter()
+        .map(|r| r.name.clone())
+        .collect::<HashSet<_>>();
 
-    log::info!("running {} benchmarks...", benchmarks.len());
+    log::info!(
+        "running {} benchmarks on {} runners...",
+        benchmarks.len(),
+        runners.len()
+    );
     log::debug!(
         "benchmarks: {}",
         benchmark_names
@@ -135,27 +1498,372 @@ pub fn run_benchmarks_on_runners(
             .collect::<Vec<_>>()
             .join(", ")
     );
+    log::debug!(
+        "runners: {}",
+        runner_names.iter().cloned().collect::<Vec<_>>().join(", ")
+    );
+    if let Some(seed) = config.shuffle_seed {
+        log::info!("shuffling run order with seed {seed}");
+    }
 
-    let mut results: HashMap<Benchmark, HashMap<Runner, RunResult>> = HashMap::new();
-    for benchmark in benchmarks {
-        let result = match run_benchmark_on_runners(benchmark, &runners) {
-            Ok(res) => res,
-            Err(e) => {
-                log::warn!(
-                    "could not run benchmark {} on runners: {e}",
-                    benchmark.benchmark.name
+    let order = run_order(benchmarks, runners, config);
+    let num_repetitions = config.repetitions.unwrap_or(1).max(1);
+    if num_repetitions > 1 {
+        log::info!("repeating the suite {num_repetitions} times");
+    }
+    let unsupported = benchmarks
+        .iter()
+        .flat_map(|b| runners.iter().map(move |r| (b, r)))
+        .filter(|(b, r)| !r.supports(&b.benchmark))
+        .map(|(b, r)| (b.benchmark.name.clone(), r.name.clone()))
+        .collect::<Vec<_>>();
+    for (benchmark_name, runner_name) in &unsupported {
+        log::info!(
+            "skipping benchmark {benchmark_name} on runner {runner_name}, it is unsupported"
+        );
+    }
+    let mut output = RunOutput {
+        unsupported,
+        order: (0..num_repetitions)
+            .flat_map(|_| {
+                order
+                    .iter()
+                    .map(|(b, r)| (b.benchmark.name.clone(), r.name.clone()))
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    // Benchmark, number of runs, and wall time of every run so far.
+    let mut run_times = Vec::<(String, u64, Duration)>::new();
+    let mut repeated_results = HashMap::<Benchmark, HashMap<Runner, Vec<RunResult>>>::new();
+    let mut completed_runs = HashMap::<(String, String, u32), RunResult>::new();
+    if let Some(partial_results_path) = config
+        .partial_results_path
+        .as_ref()
+        .filter(|p| config.resume && p.exists())
+    {
+        completed_runs.extend(
+            read_partial_runs(partial_results_path)
+                .map_err(EvmBenchError::Run)?
+                .into_iter()
+                .map(|run| {
+                    (
+                        (run.benchmark_name, run.runner_name, run.result.repetition),
+                        run.result,
+                    )
+                }),
+        );
+        log::info!(
+            "resuming with {} completed runs from {}",
+            completed_runs.len(),
+            partial_results_path.display()
+        );
+    }
+    let mut done = 0;
+    'repetitions: for repetition in 0..num_repetitions {
+        for (benchmark, runner) in &order {
+            if interrupted() {
+                log::warn!("interrupted, not starting any more runs");
+                output.incomplete = true;
+                break 'repetitions;
+            }
+            if config.past_deadline() {
+                log::warn!("past the deadline, not starting any more runs");
+                output.incomplete = true;
+                output.deadline_exceeded = true;
+                break 'repetitions;
+            }
+            let total = output.order.len();
+            let mut report = |state| {
+                if state != ProgressState::Started {
+                    done += 1;
+                }
+                on_progress(Progress {
+                    item: (*benchmark, *runner),
+                    state,
+                    done,
+                    total,
+                });
+            };
+            if let Some(result) = completed_runs.remove(&(
+                benchmark.benchmark.name.clone(),
+                runner.name.clone(),
+                repetition,
+            )) {
+                log::debug!(
+                    "already ran benchmark {} on runner {}",
+                    benchmark.benchmark.name,
+                    runner.name
                 );
+                repeated_results
+                    .entry(benchmark.benchmark.clone())
+                    .or_default()
+                    .entry((*runner).clone())
+                    .or_default()
+                    .push(result);
+                report(ProgressState::Succeeded);
                 continue;
             }
-        };
 
-        results.insert(benchmark.benchmark.clone(), result);
+            let num_runs = config
+                .num_runs_override
+                .unwrap_or(benchmark.benchmark.num_runs);
+            if let Some(time_budget) = config.time_budget {
+                let estimate = estimate_run_time(&benchmark.benchmark, num_runs, &run_times);
+                if start.elapsed() + estimate > time_budget {
+                    log::warn!(
+                        "skipping benchmark {} on runner {}, it would exceed the time budget",
+                        benchmark.benchmark.name,
+                        runner.name
+                    );
+                    let skipped = (benchmark.benchmark.name.clone(), runner.name.clone());
+                    if !output.skipped.contains(&skipped) {
+                        output.skipped.push(skipped);
+                    }
+                    report(ProgressState::Skipped);
+                    continue;
+                }
+            }
+
+            report(ProgressState::Started);
+            let run_start = Instant::now();
+            let result = run_benchmark_on_runner(benchmark, runner, config);
+            let run_time = run_start.elapsed();
+            run_times.push((benchmark.benchmark.name.clone(), num_runs, run_time));
+            output.summary.attempted += 1;
+            if output
+                .summary
+                .slowest
+                .as_ref()
+                .is_none_or(|(_, _, slowest)| run_time > *slowest)
+            {
+                output.summary.slowest = Some((
+                    benchmark.benchmark.name.clone(),
+                    runner.name.clone(),
+                    run_time,
+                ));
+            }
+            let result = match result {
+                Ok(res) => res,
+                Err(e) => {
+                    log::warn!(
+                        "could not run benchmark {} on runner {}: {e}",
+                        benchmark.benchmark.name,
+                        runner.name
+                    );
+                    let file_name = if num_repetitions > 1 {
+                        format!(
+                            "{}.{}.{repetition}.stderr.log",
+                            benchmark.benchmark.name, runner.name
+                        )
+                    } else {
+                        format!("{}.{}.stderr.log", benchmark.benchmark.name, runner.name)
+                    };
+                    let stderr_path = config.failures_path.as_ref().and_then(|failures_path| {
+                        save_failure_stderr(&e, failures_path, &file_name).unwrap_or_else(|e| {
+                            log::warn!("could not save runner stderr: {e}");
+                            None
+                        })
+                    });
+                    if let Some(stderr_path) = &stderr_path {
+                        log::warn!("saved runner stderr to {}", stderr_path.display());
+                    }
+                    output.failed.push(RunFailure {
+                        benchmark_name: benchmark.benchmark.name.clone(),
+                        runner_name: runner.name.clone(),
+                        reason: e.to_string(),
+                        stderr_path,
+                    });
+                    output.summary.failed += 1;
+                    report(ProgressState::Failed);
+                    if config.past_deadline() {
+                        log::warn!("past the deadline, not starting any more runs");
+                        output.incomplete = true;
+                        output.deadline_exceeded = true;
+                        break 'repetitions;
+                    }
+                    continue;
+                }
+            };
+            output.summary.succeeded += 1;
+            let result = RunResult {
+                repetition,
+                scaling: measure_scaling(benchmark, runner, config),
+                ..result
+            };
+            // Runners with argument templates are not asked to iterate, see
+            // `run_benchmark_on_runner`.
+            let inner_iterations = match runner.args {
+                Some(_) => 1,
+                None => benchmark.benchmark.inner_iterations.max(1),
+            };
+            output.timings.push(RunTiming {
+                benchmark_name: benchmark.benchmark.name.clone(),
+                runner_name: runner.name.clone(),
+                repetition,
+                wall_time: run_time,
+                measured_time: result
+                    .run_times
+                    .iter()
+                    .chain(&result.deploy_times)
+                    .sum::<Duration>()
+                    .mul_f64(inner_iterations as f64),
+            });
+            if let Some(partial_results_path) = &config.partial_results_path {
+                let partial_run = PartialRun {
+                    benchmark_name: benchmark.benchmark.name.clone(),
+                    runner_name: runner.name.clone(),
+                    result: result.clone(),
+                };
+                if let Err(e) = append_partial_run(partial_results_path, &partial_run) {
+                    log::warn!("could not append to partial results: {e}");
+                }
+            }
+            repeated_results
+                .entry(benchmark.benchmark.clone())
+                .or_default()
+                .entry((*runner).clone())
+                .or_default()
+                .push(result);
+            report(ProgressState::Succeeded);
+        }
+    }
+    output.results = repeated_results
+        .into_iter()
+        .map(|(benchmark, benchmark_results)| {
+            (
+                benchmark,
+                benchmark_results
+                    .into_iter()
+                    .map(|(runner, results)| (runner, merge_repetitions(results)))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    check_pass_counts(&mut output.results, config.equalize_passes);
+    output.summary.total_wall = start.elapsed();
+
+    if let Some(max_divergence) = config.verify_stability.filter(|_| !output.incomplete) {
+        output.stability_checks =
+            verify_stability(benchmarks, &output.results, config, max_divergence);
     }
 
     log::debug!(
-        "ran {} benchmarks ({} successful)",
-        benchmarks.len(),
-        results.len()
+        "ran {} benchmark runs ({} successful, {} skipped)",
+        output.order.len(),
+        output.summary.succeeded,
+        output.skipped.len()
     );
-    Ok(results)
+    log::info!(
+        "ran {}/{} benchmark runs successfully in {:?}",
+        output.summary.succeeded,
+        output.summary.attempted,
+        output.summary.total_wall
+    );
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::results::{TimeFormat, TimeUnit};
+
+    #[test]
+    fn sub_millisecond_run_times_are_kept() {
+        let scratch_path = env::temp_dir().join(format!("evm-bench-test-{}", process::id()));
+        let runner: Runner = serde_json::from_value(json!({
+            "name": "fake",
+            "entry": "sh",
+            "args": ["-c", "echo 0.9"],
+        }))
+        .unwrap();
+        let result = run_bytecode(
+            "fake",
+            "00",
+            &[],
+            1,
+            &runner,
+            &RunConfig::default(),
+            &scratch_path,
+        );
+        fs::remove_dir_all(&scratch_path).unwrap();
+
+        let run_times = result.unwrap().run_times;
+        assert_eq!(run_times, [Duration::from_micros(900)]);
+        let run_time = run_times[0];
+        assert_eq!(TimeFormat::default().format(run_time), "900µs");
+        let format = TimeFormat {
+            unit: TimeUnit::Ms,
+            precision: Some(2),
+        };
+        assert_eq!(format.format(run_time), "0.90ms");
+    }
+
+    #[test]
+    fn run_times_too_long_for_a_duration_are_ignored() {
+        let scratch_path = env::temp_dir().join(format!("evm-bench-test-{}-long", process::id()));
+        let runner: Runner = serde_json::from_value(json!({
+            "name": "fake",
+            "entry": "sh",
+            "args": ["-c", "echo 1e30; echo 2"],
+        }))
+        .unwrap();
+        let result = run_bytecode(
+            "fake",
+            "00",
+            &[],
+            1,
+            &runner,
+            &RunConfig::default(),
+            &scratch_path,
+        );
+        fs::remove_dir_all(&scratch_path).unwrap();
+
+        assert_eq!(result.unwrap().run_times, [Duration::from_millis(2)]);
+    }
+
+    #[test]
+    fn run_time_estimate_is_seeded_from_cost_and_refined_by_runs() {
+        let benchmark = |name: &str, cost: Option<BenchmarkCost>| Benchmark {
+            cost,
+            ..serde_json::from_value(json!({
+                "name": name,
+                "solc_version": "0.8.17",
+                "num_runs": 10,
+                "contract": "",
+                "build_context": "",
+                "calldata": [],
+                "weight": 1.0,
+                "mode": "call",
+            }))
+            .unwrap()
+        };
+        let expensive = benchmark("expensive", Some(BenchmarkCost::Expensive));
+        let uncosted = benchmark("uncosted", None);
+
+        assert_eq!(
+            estimate_run_time(&expensive, 10, &[]),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            estimate_run_time(&uncosted, 10, &[]),
+            Duration::from_secs(1)
+        );
+
+        let run_times = [("expensive".to_string(), 10, Duration::from_millis(50))];
+        assert_eq!(
+            estimate_run_time(&expensive, 10, &run_times),
+            Duration::from_millis(50)
+        );
+        assert_eq!(
+            estimate_run_time(&uncosted, 20, &run_times),
+            Duration::from_millis(100)
+        );
+    }
 }
@@ -0,0 +1,119 @@
+//! Summary statistics over the run times of a benchmark on a runner.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Summary statistics of a set of durations, as computed by [`summary`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Summary {
+    pub mean: Duration,
+    pub median: Duration,
+    /// Sample standard deviation (zero for fewer than two durations).
+    pub stddev: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// 95th percentile, using the nearest-rank method.
+    pub p95: Duration,
+    /// Standard deviation relative to the mean (zero if the mean is zero).
+    pub coefficient_of_variation: f64,
+}
+
+impl Summary {
+    /// How far the mean is from the median, relative to the median, or `None` if the median is
+    /// zero. A large divergence means a few outlying durations, e.g. from GC pauses or
+    /// scheduling noise, are skewing the mean.
+    pub fn mean_median_divergence(&self) -> Option<f64> {
+        if self.median.is_zero() {
+            None
+        } else {
+            Some(
+                (self.mean.as_secs_f64() - self.median.as_secs_f64()).abs()
+                    / self.median.as_secs_f64(),
+            )
+        }
+    }
+}
+
+/// How many times longer `duration` is than `reference`, or `None` if `reference` is zero and
+/// the ratio is meaningless.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use evm_bench::stats::ratio;
+///
+/// assert_eq!(ratio(Duration::from_millis(3), Duration::from_millis(2)), Some(1.5));
+/// assert_eq!(ratio(Duration::from_millis(3), Duration::ZERO), None);
+/// assert_eq!(ratio(Duration::ZERO, Duration::ZERO), None);
+/// ```
+pub fn ratio(duration: Duration, reference: Duration) -> Option<f64> {
+    (!reference.is_zero()).then(|| duration.as_secs_f64() / reference.as_secs_f64())
+}
+
+/// Computes summary statistics of `durations`. All statistics are zero if `durations` is empty.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use evm_bench::stats::summary;
+///
+/// let durations = [1, 2, 3, 4, 10].map(Duration::from_millis);
+/// let summary = summary(&durations);
+/// assert_eq!(summary.mean, Duration::from_millis(4));
+/// assert_eq!(summary.median, Duration::from_millis(3));
+/// assert_eq!(summary.min, Duration::from_millis(1));
+/// assert_eq!(summary.max, Duration::from_millis(10));
+/// assert_eq!(summary.p95, Duration::from_millis(10));
+/// ```
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use evm_bench::stats::{summary, Summary};
+///
+/// assert_eq!(summary(&[]), Summary::default());
+/// assert_eq!(summary(&[Duration::from_millis(5)]).stddev, Duration::ZERO);
+/// ```
+pub fn summary(durations: &[Duration]) -> Summary {
+    if durations.is_empty() {
+        return Summary::default();
+    }
+
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    let n = sorted.len();
+
+    let mean = sorted.iter().sum::<Duration>().div_f64(n as f64);
+    let median = if n.is_multiple_of(2) {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+    } else {
+        sorted[n / 2]
+    };
+    let stddev = if n > 1 {
+        let variance = sorted
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / (n - 1) as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    } else {
+        Duration::ZERO
+    };
+    let p95 = sorted[((0.95 * n as f64).ceil() as usize).clamp(1, n) - 1];
+    let coefficient_of_variation = if mean.is_zero() {
+        0.0
+    } else {
+        stddev.as_secs_f64() / mean.as_secs_f64()
+    };
+
+    Summary {
+        mean,
+        median,
+        stddev,
+        min: sorted[0],
+        max: sorted[n - 1],
+        p95,
+        coefficient_of_variation,
+    }
+}